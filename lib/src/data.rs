@@ -1,24 +1,53 @@
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::ffi::OsStr;
-use std::fs::DirEntry;
-use std::path::PathBuf;
+use std::fmt;
+use std::fs::{self, DirEntry};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use url::Url;
 use uuid::Uuid;
 
 use crate::{
+    diagnostics::{Diagnostic, Diagnostics, Severity},
     format_text,
-    helpers::{read_dir_entry_data, write_data},
-    traits::{CourseAssociated, Hashable, WithImage},
+    helpers::{display_path, read_dir_entry_data, write_data},
+    source_map::SourceMap,
+    traits::{CourseAssociated, Hashable, ImageRename, ImageRenaming, WithImage},
     RawCourseData,
 };
 use crate::{
-    raw_data::{RawQuestionData, RawQuestionOptionData},
+    raw_data::{RawCourseTopicData, RawQuestionData, RawQuestionOptionData, RawReferenceData},
     RawCourseEvaluationData,
 };
 
+/// A course data file after going through the formatting pipeline.
+#[derive(Clone, Debug)]
+pub struct FormattedCourseData {
+    pub path: PathBuf,
+    pub source: Vec<u8>,
+    pub formatted: String,
+    pub image_renames: Vec<ImageRename>,
+    pub data: CourseData,
+}
+
+impl FormattedCourseData {
+    pub fn is_formatted(&self) -> bool {
+        self.source == self.formatted.as_bytes() && self.image_renames.is_empty()
+    }
+
+    pub fn write(&self) -> Result<()> {
+        fs::write(&self.path, &self.formatted)?;
+
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CourseData {
     pub key: String,
@@ -26,12 +55,15 @@ pub struct CourseData {
     pub short_name: String,
     pub aliases: Vec<String>,
     pub image_file_name: Option<PathBuf>,
+    #[serde(skip)]
+    pub image_hash: Option<String>,
     pub year: Option<i16>,
     pub order: Option<i16>,
 
     #[serde(skip)]
     pub questions: Vec<QuestionData>,
     pub evaluations: Vec<CourseEvaluationData>,
+    pub topics: Vec<CourseTopicData>,
 
     pub hash: String,
 }
@@ -50,6 +82,7 @@ impl CourseData {
                 evaluation
             })
             .collect();
+        let topics = CourseTopicData::flatten(raw.topics, None);
 
         Self {
             key,
@@ -57,52 +90,135 @@ impl CourseData {
             short_name: raw.short_name,
             aliases: raw.aliases,
             image_file_name: raw.image,
+            image_hash: None,
             year: raw.year,
             order: raw.order,
             questions,
             evaluations,
+            topics,
             hash: Default::default(),
         }
     }
 
+    /// Loads, checks and formats a course data file, writing it back and renaming its images.
+    /// Problems in the file are pushed to `diagnostics`, and if any of them is an error the file is
+    /// left untouched and `None` is returned.
     pub async fn load_and_write_formatted(
         dir_entry: DirEntry,
-        mut images_path: PathBuf,
-    ) -> Result<Self> {
+        images_path: PathBuf,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Option<Self>> {
+        let formatted =
+            match Self::load_formatted(dir_entry, images_path, ImageRenaming::Apply, diagnostics)
+                .await?
+            {
+                Some(formatted) => formatted,
+                None => return Ok(None),
+            };
+
+        formatted.write()?;
+
+        Ok(Some(formatted.data))
+    }
+
+    /// Loads, checks and formats a course data file in memory, without writing it back.
+    pub async fn load_formatted(
+        dir_entry: DirEntry,
+        images_path: PathBuf,
+        renaming: ImageRenaming,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Option<FormattedCourseData>> {
         let path = dir_entry.path();
-        let mut data = Self::load(path.clone(), dir_entry)?;
+        let raw_data = read_dir_entry_data(dir_entry)?;
+
+        Self::format_source(path, raw_data, images_path, renaming, diagnostics).await
+    }
+
+    /// Checks and formats `raw_data` as the contents of the course data file at `path`, which
+    /// doesn't need to match what is on disk.
+    pub async fn format_source(
+        path: PathBuf,
+        raw_data: Vec<u8>,
+        mut images_path: PathBuf,
+        renaming: ImageRenaming,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Option<FormattedCourseData>> {
+        let source_map = SourceMap::new(&raw_data);
+
+        let mut data = match Self::parse(&path, &raw_data) {
+            Ok(data) => data,
+            Err(error) => {
+                diagnostics.push(Diagnostic::from_parse_error(&error, &source_map).with_file(path));
+
+                return Ok(None);
+            }
+        };
+
+        images_path.push(data.key.clone());
+
+        let mut course_diagnostics = data.check();
+        course_diagnostics.extend(data.check_images(&images_path));
+
+        let has_errors = course_diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error);
+
+        diagnostics.extend_for_file(path.clone(), &source_map, course_diagnostics);
+
+        if has_errors {
+            return Ok(None);
+        }
 
-        data.check()?;
         data.clean();
         data.sort();
 
-        images_path.push(data.key.clone());
-        data.format(images_path).await?;
+        let image_renames = data.format(images_path, renaming).await?;
         data.set_data();
 
-        data.clone().write(path)?;
+        let formatted = format!("{}\n", data.to_formatted_json()?);
 
-        Ok(data)
+        Ok(Some(FormattedCourseData {
+            path,
+            source: raw_data,
+            formatted,
+            image_renames,
+            data,
+        }))
     }
 
     pub fn load(path: PathBuf, dir_entry: DirEntry) -> Result<Self> {
         let raw_data = read_dir_entry_data(dir_entry)?;
 
+        Self::parse(&path, &raw_data)
+    }
+
+    fn parse(path: &Path, raw_data: &[u8]) -> Result<Self> {
         let key = path
             .file_stem()
             .and_then(OsStr::to_str)
             .expect("invalid file name")
             .to_owned();
-        let raw_course_data = RawCourseData::from_slice(&raw_data[..])?;
+        let raw_course_data = RawCourseData::from_slice(raw_data)?;
 
         Ok(Self::new(key, raw_course_data))
     }
 
     pub fn write(self, path: PathBuf) -> Result<()> {
-        let raw = self.into();
-        let raw_data = serde_json::to_string_pretty::<RawCourseData>(&raw)?;
+        write_data(path, self.to_formatted_json()?)
+    }
 
-        write_data(path, raw_data)
+    fn to_formatted_json(&self) -> Result<String> {
+        let raw: RawCourseData = self.clone().into();
+
+        Ok(serde_json::to_string_pretty(&raw)?)
+    }
+
+    /// The images of the course and its questions.
+    pub fn images(&self) -> Vec<ImageData> {
+        self.image_data()
+            .into_iter()
+            .chain(self.questions.iter().filter_map(WithImage::image_data))
+            .collect()
     }
 
     fn sort(&mut self) {
@@ -131,12 +247,152 @@ impl CourseData {
         }
     }
 
-    fn check(&self) -> Result<()> {
-        for question in &self.questions {
-            question.check()?;
+    fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        for (index, question) in self.questions.iter().enumerate() {
+            diagnostics.extend(question.check(&format!("/questions/{index}")));
         }
 
-        Ok(())
+        diagnostics.extend(self.check_question_evaluations());
+        diagnostics.extend(self.check_topics());
+        diagnostics.extend(self.check_question_topics());
+
+        diagnostics
+    }
+
+    /// Checks that the images of the course and its questions exist in `images_path`.
+    fn check_images(&self, images_path: &Path) -> Vec<Diagnostic> {
+        let course_image = self
+            .image_file_name
+            .iter()
+            .map(|image_file_name| ("/image".to_owned(), image_file_name, None));
+        let question_images = self
+            .questions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, question)| {
+                Some((
+                    format!("/questions/{index}/image"),
+                    question.image_file_name.as_ref()?,
+                    Some(question.id),
+                ))
+            });
+
+        course_image
+            .chain(question_images)
+            .filter(|(_, image_file_name, _)| !images_path.join(image_file_name).is_file())
+            .map(|(path, image_file_name, question_id)| {
+                let diagnostic = Diagnostic::error(
+                    path,
+                    format!(
+                        "Image {} doesn't exist in {}",
+                        image_file_name.display(),
+                        display_path(images_path).display()
+                    ),
+                );
+
+                match question_id {
+                    Some(question_id) => diagnostic.with_question_id(question_id),
+                    None => diagnostic,
+                }
+            })
+            .collect()
+    }
+
+    fn check_question_evaluations(&self) -> Vec<Diagnostic> {
+        let evaluation_keys: HashSet<_> = self
+            .evaluations
+            .iter()
+            .map(|evaluation| CourseEvaluationData::do_full_key(&self.key, &evaluation.key))
+            .collect();
+
+        self.questions
+            .iter()
+            .enumerate()
+            .filter(|(_, question)| {
+                !evaluation_keys.contains(&CourseEvaluationData::do_full_key(
+                    &self.key,
+                    &question.evaluation,
+                ))
+            })
+            .map(|(index, question)| {
+                Diagnostic::error(
+                    format!("/questions/{index}/evaluation"),
+                    format!(
+                        "Evaluation {} is not declared in course {}",
+                        question.evaluation, self.key
+                    ),
+                )
+                .with_question_id(question.id)
+            })
+            .collect()
+    }
+
+    fn check_topics(&self) -> Vec<Diagnostic> {
+        let mut seen_keys = HashSet::new();
+
+        self.topics
+            .iter()
+            .zip(self.topic_paths())
+            .filter(|(topic, _)| !seen_keys.insert(&topic.key))
+            .map(|(topic, path)| {
+                Diagnostic::error(
+                    format!("{path}/key"),
+                    format!("Topic {} is declared more than once", topic.key),
+                )
+            })
+            .collect()
+    }
+
+    /// The JSON pointer of every topic, given they are flattened in depth-first order.
+    fn topic_paths(&self) -> Vec<String> {
+        let mut ancestors: Vec<(&str, String)> = vec![];
+
+        self.topics
+            .iter()
+            .map(|topic| {
+                while ancestors
+                    .last()
+                    .map_or(false, |(key, _)| Some(*key) != topic.parent_key.as_deref())
+                {
+                    ancestors.pop();
+                }
+
+                let index = topic.order.unwrap_or_default() - 1;
+                let path = match ancestors.last() {
+                    Some((_, parent_path)) => format!("{parent_path}/subtopics/{index}"),
+                    None => format!("/topics/{index}"),
+                };
+
+                ancestors.push((&topic.key, path.clone()));
+
+                path
+            })
+            .collect()
+    }
+
+    fn check_question_topics(&self) -> Vec<Diagnostic> {
+        let topic_keys: HashSet<_> = self.topics.iter().map(|topic| &topic.key).collect();
+
+        self.questions
+            .iter()
+            .enumerate()
+            .flat_map(|(index, question)| {
+                question
+                    .topics
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, topic)| !topic_keys.contains(topic))
+                    .map(move |(topic_index, topic)| {
+                        Diagnostic::error(
+                            format!("/questions/{index}/topics/{topic_index}"),
+                            format!("Topic {topic} is not declared in course {}", self.key),
+                        )
+                        .with_question_id(question.id)
+                    })
+            })
+            .collect()
     }
 
     fn set_data(&mut self) {
@@ -148,17 +404,29 @@ impl CourseData {
             evaluation.set_data(self.key.clone());
         }
 
+        for topic in &mut self.topics {
+            topic.set_data(self.key.clone());
+        }
+
         self.set_hash();
     }
 
-    async fn format(&mut self, images_path: PathBuf) -> Result<()> {
-        self.format_image(images_path.clone()).await?;
+    async fn format(
+        &mut self,
+        images_path: PathBuf,
+        renaming: ImageRenaming,
+    ) -> Result<Vec<ImageRename>> {
+        let mut image_renames: Vec<_> = self
+            .format_image(images_path.clone(), renaming)
+            .await?
+            .into_iter()
+            .collect();
 
         for question in &mut self.questions {
-            question.format(images_path.clone()).await?;
+            image_renames.extend(question.format(images_path.clone(), renaming).await?);
         }
 
-        Ok(())
+        Ok(image_renames)
     }
 }
 
@@ -184,6 +452,14 @@ impl WithImage for CourseData {
     fn replace_image_file_name(&mut self, new_file_name: PathBuf) {
         self.image_file_name.replace(new_file_name);
     }
+
+    fn image_hash(&self) -> Option<&str> {
+        self.image_hash.as_deref()
+    }
+
+    fn set_image_hash(&mut self, image_hash: String) {
+        self.image_hash.replace(image_hash);
+    }
 }
 
 impl Hashable for CourseData {
@@ -199,6 +475,10 @@ impl Hashable for CourseData {
             bytes.extend(image_file_name.to_string_lossy().as_bytes());
         }
 
+        if let Some(image_hash) = &self.image_hash {
+            bytes.extend(image_hash.as_bytes());
+        }
+
         if let Some(year) = self.year {
             bytes.extend(&year.to_be_bytes());
         }
@@ -217,6 +497,7 @@ impl Hashable for CourseData {
                 .iter()
                 .flat_map(|evaluation| evaluation.hash.as_bytes()),
         );
+        bytes.extend(self.topics.iter().flat_map(|topic| topic.hash.as_bytes()));
 
         bytes
     }
@@ -231,13 +512,19 @@ pub struct QuestionData {
     pub id: Uuid,
 
     pub course_key: Option<String>,
+    pub kind: QuestionKind,
     pub evaluation: String,
-    pub source: String,
+    pub source: QuestionSource,
     pub asked_at: Option<NaiveDate>,
     pub text: String,
     pub image_file_name: Option<PathBuf>,
     #[serde(skip)]
+    pub image_hash: Option<String>,
+    #[serde(skip)]
     pub question_options: Vec<QuestionOptionData>,
+    pub explanation: Option<String>,
+    pub references: Vec<ReferenceData>,
+    pub topics: Vec<String>,
 
     pub hash: String,
 }
@@ -249,37 +536,48 @@ impl QuestionData {
         image_file_name: Option<PathBuf>,
         question_options: Vec<QuestionOptionData>,
         evaluation: String,
-        source: String,
+        source: QuestionSource,
         asked_at: Option<NaiveDate>,
     ) -> Self {
         Self {
             id,
             course_key: None,
+            kind: Default::default(),
             evaluation,
             source,
             asked_at,
             text,
             image_file_name,
+            image_hash: None,
             question_options,
+            explanation: None,
+            references: vec![],
+            topics: vec![],
             hash: Default::default(),
         }
     }
 
     fn sort_options(&mut self) {
-        self.question_options.sort_by(|a, b| {
-            if a.correct {
-                Ordering::Less
-            } else if b.correct {
-                Ordering::Greater
-            } else {
-                a.text.cmp(&b.text)
-            }
-        })
+        match self.kind {
+            QuestionKind::SingleChoice | QuestionKind::MultipleChoice => self
+                .question_options
+                .sort_by(|a, b| b.correct.cmp(&a.correct).then_with(|| a.text.cmp(&b.text))),
+            // Every statement is either true or false, so their order shouldn't hint at which.
+            QuestionKind::TrueFalse => self.question_options.sort_by(|a, b| a.text.cmp(&b.text)),
+        }
     }
 
     fn clean(&mut self) {
         self.remove_empty_options();
         self.deduplicate_options();
+        self.deduplicate_topics();
+    }
+
+    fn deduplicate_topics(&mut self) {
+        let mut seen_topics = HashSet::new();
+
+        self.topics
+            .retain(|topic| seen_topics.insert(topic.clone()));
     }
 
     fn deduplicate_options(&mut self) {
@@ -293,6 +591,7 @@ impl QuestionData {
 
     fn eq_data(&self, other: &Self) -> bool {
         self.text == other.text
+            && self.kind == other.kind
             && self.evaluation == other.evaluation
             && self.question_options.len() == other.question_options.len()
             && self
@@ -301,13 +600,17 @@ impl QuestionData {
                 .all(|a| other.question_options.iter().any(|b| a.eq_data(b)))
     }
 
-    fn check(&self) -> Result<()> {
+    fn check(&self, path: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
         if self.question_options.len() < 2 || self.question_options.len() > 5 {
-            bail!(
-                "Question {} has {} option(s)",
-                self.id,
-                self.question_options.len()
-            );
+            diagnostics.push(Diagnostic::error(
+                format!("{path}/options"),
+                format!(
+                    "Question has {} option(s), expected between 2 and 5",
+                    self.question_options.len()
+                ),
+            ));
         }
 
         let correct_count = self
@@ -316,23 +619,66 @@ impl QuestionData {
             .filter(|option| option.correct)
             .count();
 
-        if correct_count != 1 {
-            bail!("Question {} has {correct_count} correct options", self.id)
+        let expected_correct_count = match self.kind {
+            QuestionKind::SingleChoice if correct_count != 1 => Some("exactly 1"),
+            QuestionKind::MultipleChoice if correct_count == 0 => Some("at least 1"),
+            QuestionKind::SingleChoice | QuestionKind::MultipleChoice => None,
+            QuestionKind::TrueFalse => None,
+        };
+
+        if let Some(expected_correct_count) = expected_correct_count {
+            diagnostics.push(Diagnostic::error(
+                format!("{path}/options"),
+                format!(
+                    "{} question has {correct_count} correct options, expected {expected_correct_count}",
+                    self.kind
+                ),
+            ));
         }
 
-        Ok(())
+        for (index, question_option) in self.question_options.iter().enumerate() {
+            if question_option.text.is_empty() {
+                diagnostics.push(Diagnostic::warning(
+                    format!("{path}/options/{index}/text"),
+                    "Option is empty and will be removed",
+                ));
+            }
+        }
+
+        for (index, reference) in self.references.iter().enumerate() {
+            diagnostics.extend(reference.check(&format!("{path}/references/{index}")));
+        }
+
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.with_question_id(self.id))
+            .collect()
     }
 
-    async fn format(&mut self, images_path: PathBuf) -> Result<()> {
+    async fn format(
+        &mut self,
+        images_path: PathBuf,
+        renaming: ImageRenaming,
+    ) -> Result<Option<ImageRename>> {
         self.text = format_text(&self.text);
 
-        self.format_image(images_path).await?;
+        let image_rename = self.format_image(images_path, renaming).await?;
 
         for question_option in self.question_options.iter_mut() {
             question_option.format();
         }
 
-        Ok(())
+        self.explanation = self
+            .explanation
+            .as_deref()
+            .map(format_text)
+            .filter(|explanation| !explanation.is_empty());
+
+        for reference in self.references.iter_mut() {
+            reference.format();
+        }
+
+        Ok(image_rename)
     }
 
     fn set_data(&mut self, course_key: String) {
@@ -374,6 +720,14 @@ impl WithImage for QuestionData {
     fn replace_image_file_name(&mut self, new_file_name: PathBuf) {
         self.image_file_name.replace(new_file_name);
     }
+
+    fn image_hash(&self) -> Option<&str> {
+        self.image_hash.as_deref()
+    }
+
+    fn set_image_hash(&mut self, image_hash: String) {
+        self.image_hash.replace(image_hash);
+    }
 }
 
 impl Hashable for QuestionData {
@@ -389,19 +743,40 @@ impl Hashable for QuestionData {
             bytes.extend(image_file_name.to_string_lossy().as_bytes());
         }
 
+        if let Some(image_hash) = &self.image_hash {
+            bytes.extend(image_hash.as_bytes());
+        }
+
         bytes.extend(
             self.question_options
                 .iter()
                 .flat_map(|question_option| question_option.hash.as_bytes()),
         );
 
+        // Questions of the default kind hash as they did before kinds existed, so they aren't synced
+        // again for no reason.
+        if !self.kind.is_single_choice() {
+            bytes.extend(self.kind.as_str().as_bytes());
+        }
+
         bytes.extend(self.evaluation.as_bytes());
-        bytes.extend(self.source.as_bytes());
+        bytes.extend(self.source.as_str().as_bytes());
 
         if let Some(asked_at) = self.asked_at {
             bytes.extend(asked_at.to_string().as_bytes());
         }
 
+        if let Some(explanation) = &self.explanation {
+            bytes.extend(explanation.as_bytes());
+        }
+
+        bytes.extend(
+            self.references
+                .iter()
+                .flat_map(ReferenceData::hashable_data),
+        );
+        bytes.extend(self.topics.iter().flat_map(|topic| topic.as_bytes()));
+
         bytes
     }
 
@@ -414,15 +789,173 @@ impl From<RawQuestionData> for QuestionData {
     fn from(raw: RawQuestionData) -> Self {
         let options = raw.options.into_iter().map(Into::into).collect();
 
-        Self::new(
-            raw.id.unwrap_or_else(|| Uuid::new_v4()),
-            raw.text,
-            raw.image,
-            options,
-            raw.evaluation,
-            raw.source,
-            raw.asked_at,
-        )
+        Self {
+            kind: raw.kind,
+            explanation: raw.explanation,
+            references: raw.references.into_iter().map(Into::into).collect(),
+            topics: raw.topics,
+            ..Self::new(
+                raw.id.unwrap_or_else(Uuid::new_v4),
+                raw.text,
+                raw.image,
+                options,
+                raw.evaluation,
+                raw.source,
+                raw.asked_at,
+            )
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionKind {
+    /// Exactly one of the options is correct.
+    #[default]
+    SingleChoice,
+    /// One or more of the options are correct.
+    MultipleChoice,
+    /// Every option is a statement to be marked as true or false, true ones being correct. Any
+    /// number of statements may be true, including none or all of them.
+    TrueFalse,
+}
+
+impl QuestionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SingleChoice => "single_choice",
+            Self::MultipleChoice => "multiple_choice",
+            Self::TrueFalse => "true_false",
+        }
+    }
+
+    pub fn is_single_choice(&self) -> bool {
+        *self == Self::SingleChoice
+    }
+}
+
+impl fmt::Display for QuestionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionSource {
+    Partial,
+    Exam,
+    SelfAssessment,
+    Other,
+}
+
+impl QuestionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Partial => "partial",
+            Self::Exam => "exam",
+            Self::SelfAssessment => "self_assessment",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for QuestionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for QuestionSource {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        [Self::Partial, Self::Exam, Self::SelfAssessment, Self::Other]
+            .into_iter()
+            .find(|question_source| question_source.as_str() == source)
+            .ok_or_else(|| {
+                anyhow!(
+                    "unknown question source {source}, expected partial, exam, self_assessment or \
+                     other"
+                )
+            })
+    }
+}
+
+/// A bibliography reference backing up a question's answer.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct ReferenceData {
+    pub book: Option<String>,
+    pub chapter: Option<String>,
+    pub page: Option<String>,
+    pub url: Option<Url>,
+}
+
+impl ReferenceData {
+    fn check(&self, path: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        if self.book.is_none() && self.url.is_none() {
+            diagnostics.push(Diagnostic::error(
+                path,
+                "Reference has neither a book nor a URL",
+            ));
+        }
+
+        if self.book.is_none() && (self.chapter.is_some() || self.page.is_some()) {
+            diagnostics.push(Diagnostic::error(
+                path,
+                "Reference has a chapter or page but no book",
+            ));
+        }
+
+        if let Some(url) = &self.url {
+            if !matches!(url.scheme(), "http" | "https") {
+                diagnostics.push(Diagnostic::error(
+                    format!("{path}/url"),
+                    format!("Reference URL {url} is not a web URL"),
+                ));
+            }
+        }
+
+        diagnostics
+    }
+
+    fn format(&mut self) {
+        for field in [&mut self.book, &mut self.chapter, &mut self.page] {
+            *field = field
+                .as_deref()
+                .map(format_text)
+                .filter(|value| !value.is_empty());
+        }
+    }
+
+    fn hashable_data(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        for value in [&self.book, &self.chapter, &self.page]
+            .into_iter()
+            .flatten()
+        {
+            bytes.extend(value.as_bytes());
+        }
+
+        if let Some(url) = &self.url {
+            bytes.extend(url.as_str().as_bytes());
+        }
+
+        bytes
+    }
+}
+
+impl From<RawReferenceData> for ReferenceData {
+    fn from(raw: RawReferenceData) -> Self {
+        Self {
+            book: raw.book,
+            chapter: raw.chapter,
+            page: raw.page,
+            url: raw.url,
+        }
     }
 }
 
@@ -495,7 +1028,7 @@ impl Hashable for QuestionOptionData {
 impl From<RawQuestionOptionData> for QuestionOptionData {
     fn from(raw: RawQuestionOptionData) -> Self {
         Self::new(
-            raw.id.unwrap_or_else(|| Uuid::new_v4()),
+            raw.id.unwrap_or_else(Uuid::new_v4),
             raw.text,
             raw.correct.unwrap_or(false),
             raw.explanation,
@@ -574,4 +1107,298 @@ impl From<RawCourseEvaluationData> for CourseEvaluationData {
     }
 }
 
+/// A topic of a course, flattened out of the topic tree declared in its data file.
+#[derive(Serialize, Deserialize, PartialEq, Hash, Eq, Clone, Debug)]
+pub struct CourseTopicData {
+    pub course_key: Option<String>,
+    pub key: String,
+    pub name: String,
+    pub parent_key: Option<String>,
+    pub hash: String,
+    pub order: Option<i16>,
+}
+
+impl CourseTopicData {
+    /// Flattens `raw_topics` and their subtopics in depth-first order, numbering each topic among
+    /// its siblings.
+    fn flatten(raw_topics: Vec<RawCourseTopicData>, parent_key: Option<&str>) -> Vec<Self> {
+        let mut topics = vec![];
+
+        for (index, raw_topic) in raw_topics.into_iter().enumerate() {
+            topics.push(Self {
+                course_key: None,
+                key: raw_topic.key.clone(),
+                name: raw_topic.name,
+                parent_key: parent_key.map(ToOwned::to_owned),
+                hash: Default::default(),
+                order: Some(index as i16 + 1),
+            });
+            topics.extend(Self::flatten(raw_topic.subtopics, Some(&raw_topic.key)));
+        }
+
+        topics
+    }
+
+    pub fn full_key(&self) -> String {
+        CourseEvaluationData::do_full_key(self.course_key(), &self.key)
+    }
+
+    fn set_data(&mut self, course_key: String) {
+        self.set_course_key(course_key);
+        self.set_hash();
+    }
+}
+
+impl CourseAssociated for CourseTopicData {
+    fn course_key(&self) -> &str {
+        self.course_key
+            .as_ref()
+            .expect("course key not set in course topic")
+    }
+
+    fn set_course_key(&mut self, course_key: String) {
+        self.course_key.replace(course_key);
+    }
+}
+
+impl Hashable for CourseTopicData {
+    fn hashable_data(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        bytes.extend(self.course_key().as_bytes());
+        bytes.extend(self.key.as_bytes());
+        bytes.extend(self.name.as_bytes());
+
+        if let Some(parent_key) = &self.parent_key {
+            bytes.extend(parent_key.as_bytes());
+        }
+
+        if let Some(order) = self.order {
+            bytes.extend(&order.to_be_bytes());
+        }
+
+        bytes
+    }
+
+    fn set_hash(&mut self) {
+        self.hash = self.hash_data();
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct ImageData {
+    pub path: String,
+    pub hash: String,
+}
+
 pub const COURSE_EVALUATION_KEY_SEPARATOR: &str = "/";
+
+#[cfg(any(test, feature = "test-data"))]
+impl CourseData {
+    /// Builds course data from the contents of a data file the way the formatting pipeline does,
+    /// leaving out images.
+    pub fn from_json(key: &str, raw: serde_json::Value) -> Self {
+        let mut data = Self::new(key.to_owned(), serde_json::from_value(raw).unwrap());
+
+        data.clean();
+        data.sort();
+        data.set_data();
+
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_data::{course, question};
+
+    fn course_with_question(kind: &str, options: &[(&str, bool)]) -> CourseData {
+        let options: Vec<_> = options
+            .iter()
+            .map(|(text, correct)| json!({ "text": text, "correct": correct }))
+            .collect();
+
+        CourseData::from_json(
+            "cardio",
+            json!({
+                "name": "Cardio",
+                "short_name": "Cardio",
+                "aliases": [],
+                "year": 2,
+                "questions": [{
+                    "kind": kind,
+                    "evaluation": "parcial_1",
+                    "source": "partial",
+                    "text": "Question",
+                    "options": options,
+                }],
+                "evaluations": [{ "key": "parcial_1", "name": "Parcial 1" }],
+            }),
+        )
+    }
+
+    #[test]
+    fn check_expects_correct_options_by_kind() {
+        let cases = [
+            (
+                "single_choice",
+                0,
+                Some("single_choice question has 0 correct options, expected exactly 1"),
+            ),
+            ("single_choice", 1, None),
+            (
+                "single_choice",
+                2,
+                Some("single_choice question has 2 correct options, expected exactly 1"),
+            ),
+            (
+                "multiple_choice",
+                0,
+                Some("multiple_choice question has 0 correct options, expected at least 1"),
+            ),
+            ("multiple_choice", 1, None),
+            ("multiple_choice", 3, None),
+            ("true_false", 0, None),
+            ("true_false", 2, None),
+            ("true_false", 3, None),
+        ];
+
+        for (kind, correct_count, expected_message) in cases {
+            let options: Vec<_> = ["A", "B", "C"]
+                .iter()
+                .enumerate()
+                .map(|(index, text)| (*text, index < correct_count))
+                .collect();
+
+            let diagnostics = course_with_question(kind, &options).check();
+            let messages: Vec<_> = diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.message.as_str())
+                .collect();
+
+            assert_eq!(
+                messages,
+                expected_message.into_iter().collect::<Vec<_>>(),
+                "{kind} with {correct_count} correct"
+            );
+
+            if expected_message.is_some() {
+                assert_eq!(diagnostics[0].path, "/questions/0/options");
+                assert_eq!(diagnostics[0].severity, Severity::Error);
+            }
+        }
+    }
+
+    #[test]
+    fn check_expects_two_to_five_options_of_every_kind() {
+        for kind in ["single_choice", "multiple_choice", "true_false"] {
+            let diagnostics = course_with_question(kind, &[("A", true)]).check();
+
+            assert!(
+                diagnostics.iter().any(|diagnostic| diagnostic.message
+                    == "Question has 1 option(s), expected between 2 and 5"),
+                "{kind}"
+            );
+        }
+    }
+
+    #[test]
+    fn sort_options_puts_correct_options_first_except_for_true_false() {
+        let options = [("D", false), ("C", true), ("B", false), ("A", true)];
+
+        for (kind, expected) in [
+            ("single_choice", ["A", "C", "B", "D"]),
+            ("multiple_choice", ["A", "C", "B", "D"]),
+            ("true_false", ["A", "B", "C", "D"]),
+        ] {
+            let course_data = course_with_question(kind, &options);
+            let texts: Vec<_> = course_data.questions[0]
+                .question_options
+                .iter()
+                .map(|option| option.text.as_str())
+                .collect();
+
+            assert_eq!(texts, expected, "{kind}");
+        }
+    }
+
+    #[tokio::test]
+    async fn format_source_reports_missing_images() {
+        let raw_data = serde_json::to_vec_pretty(&json!({
+            "name": "Cardio",
+            "short_name": "Cardio",
+            "aliases": [],
+            "questions": [{
+                "evaluation": "parcial_1",
+                "source": "partial",
+                "text": "Question",
+                "image": "missing.png",
+                "options": [
+                    { "text": "A", "correct": true },
+                    { "text": "B", "correct": false },
+                ],
+            }],
+            "evaluations": [{ "key": "parcial_1", "name": "Parcial 1" }],
+        }))
+        .unwrap();
+        let images_path = std::env::temp_dir().join(format!("images-{}", Uuid::new_v4()));
+        let mut diagnostics = Diagnostics::default();
+
+        let formatted = CourseData::format_source(
+            PathBuf::from("data/cardio.json"),
+            raw_data,
+            images_path.clone(),
+            ImageRenaming::Plan,
+            &mut diagnostics,
+        )
+        .await
+        .unwrap();
+
+        let diagnostics: Vec<_> = diagnostics.iter().collect();
+
+        assert!(formatted.is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "/questions/0/image");
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(
+            diagnostics[0].message,
+            format!(
+                "Image missing.png doesn't exist in {}",
+                images_path.join("cardio").display()
+            )
+        );
+        assert!(diagnostics[0]
+            .location
+            .as_ref()
+            .unwrap()
+            .snippet
+            .contains("missing.png"));
+    }
+
+    #[test]
+    fn single_choice_questions_keep_the_hash_they_had_before_kinds() {
+        let course_data = course("cardio", vec![question(1, "Question")]);
+
+        assert_eq!(
+            course_data.questions[0].hash,
+            "5cdc9fde02f7af55433da468da723ec356fb4f2bcdacc9e9a8f1d09d8483ca9e"
+        );
+    }
+
+    #[test]
+    fn question_kind_changes_the_hash() {
+        let hashes: HashSet<_> = ["single_choice", "multiple_choice", "true_false"]
+            .into_iter()
+            .map(|kind| {
+                course_with_question(kind, &[("A", true), ("B", true)]).questions[0]
+                    .hash
+                    .clone()
+            })
+            .collect();
+
+        assert_eq!(hashes.len(), 3);
+    }
+}
@@ -0,0 +1,212 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{display_path, SourceLocation, SourceMap};
+
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => f.write_str("warning"),
+            Self::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// A problem found in a course data file, located by a JSON pointer into the file.
+#[derive(Serialize, Clone, Debug)]
+pub struct Diagnostic {
+    pub file: Option<PathBuf>,
+    pub question_id: Option<Uuid>,
+    pub path: String,
+    pub location: Option<SourceLocation>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, path.into(), message.into())
+    }
+
+    pub fn warning(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, path.into(), message.into())
+    }
+
+    fn new(severity: Severity, path: String, message: String) -> Self {
+        Self {
+            file: None,
+            question_id: None,
+            path,
+            location: None,
+            severity,
+            message,
+        }
+    }
+
+    /// Builds a diagnostic for a file that `serde_json` failed to parse, located at the position
+    /// it reports.
+    pub fn from_parse_error(error: &anyhow::Error, source_map: &SourceMap) -> Self {
+        match error.downcast_ref::<serde_json::Error>() {
+            Some(json_error) if json_error.line() > 0 => {
+                let message = json_error.to_string();
+                let position_suffix = format!(
+                    " at line {} column {}",
+                    json_error.line(),
+                    json_error.column()
+                );
+
+                let mut diagnostic = Self::error(
+                    "",
+                    message.strip_suffix(&position_suffix).unwrap_or(&message),
+                );
+                diagnostic.location =
+                    source_map.locate_line_byte_column(json_error.line(), json_error.column());
+
+                diagnostic
+            }
+            _ => Self::error("", format!("{error:#}")),
+        }
+    }
+
+    pub fn with_file(mut self, file: PathBuf) -> Self {
+        self.file.replace(file);
+        self
+    }
+
+    pub fn with_question_id(mut self, question_id: Uuid) -> Self {
+        self.question_id.replace(question_id);
+        self
+    }
+
+    pub fn with_location(mut self, location: SourceLocation) -> Self {
+        self.location.replace(location);
+        self
+    }
+
+    /// Sets the location from the JSON pointer, unless it is already known.
+    pub fn locate(mut self, source_map: &SourceMap) -> Self {
+        if self.location.is_none() && !self.path.is_empty() {
+            self.location = source_map.locate(&self.path);
+        }
+
+        self
+    }
+
+    pub fn display_file(&self) -> Option<PathBuf> {
+        Some(display_path(self.file.as_ref()?))
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {}", self.severity, self.message)?;
+        write!(f, "  -->")?;
+
+        if let Some(file) = self.display_file() {
+            write!(f, " {}", file.display())?;
+
+            if let Some(location) = &self.location {
+                write!(f, ":{}:{}", location.line, location.column)?;
+            }
+        }
+
+        if !self.path.is_empty() {
+            write!(f, " at {}", self.path)?;
+        }
+
+        if let Some(question_id) = self.question_id {
+            write!(f, " (question {question_id})")?;
+        }
+
+        writeln!(f)?;
+
+        if let Some(location) = &self.location {
+            let line_number = location.line.to_string();
+            let gutter = " ".repeat(line_number.len());
+
+            writeln!(f, "{gutter} |")?;
+            writeln!(f, "{line_number} | {}", location.snippet)?;
+            writeln!(f, "{gutter} | {}^", " ".repeat(location.column - 1))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects every problem found while loading course data, so they can be reported together.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Adds diagnostics found in `file`, locating them in its source.
+    pub fn extend_for_file(
+        &mut self,
+        file: PathBuf,
+        source_map: &SourceMap,
+        diagnostics: impl IntoIterator<Item = Diagnostic>,
+    ) {
+        self.diagnostics.extend(
+            diagnostics
+                .into_iter()
+                .map(|diagnostic| diagnostic.with_file(file.clone()).locate(source_map)),
+        );
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// Fails if any of the diagnostics is an error; warnings alone don't.
+    pub fn into_result(self) -> Result<()> {
+        if self.has_errors() {
+            bail!(
+                "found {} error(s) and {} warning(s)",
+                self.count(Severity::Error),
+                self.count(Severity::Warning)
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in self.iter() {
+            writeln!(f, "{diagnostic}")?;
+        }
+
+        Ok(())
+    }
+}
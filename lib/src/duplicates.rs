@@ -0,0 +1,248 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::{CourseData, QuestionData};
+
+const SHINGLE_LENGTH: usize = 5;
+const BANDS: usize = 32;
+const ROWS_PER_BAND: usize = 4;
+const SIGNATURE_LENGTH: usize = BANDS * ROWS_PER_BAND;
+
+/// Two questions whose text and options look alike, scored from 0 to 1.
+#[derive(Clone, Debug)]
+pub struct DuplicatePair<'a> {
+    pub first: &'a QuestionData,
+    pub second: &'a QuestionData,
+    pub score: f64,
+}
+
+/// Finds pairs of questions scoring at least `min_score`, within and across courses.
+///
+/// Each question is reduced to the character shingles of its normalized text and options, so
+/// accents, punctuation, casing and option order don't matter. Candidates are picked by
+/// locality-sensitive hashing of MinHash signatures, then scored by the Jaccard similarity of their
+/// shingles.
+pub fn find_duplicates(courses_data: &[CourseData], min_score: f64) -> Vec<DuplicatePair<'_>> {
+    let questions: Vec<_> = courses_data
+        .iter()
+        .flat_map(|course_data| &course_data.questions)
+        .map(|question| (question, shingles(question)))
+        .filter(|(_, shingles)| !shingles.is_empty())
+        .collect();
+
+    let signatures: Vec<_> = questions
+        .iter()
+        .map(|(_, shingles)| min_hash_signature(shingles))
+        .collect();
+
+    let mut buckets: HashMap<(usize, &[u64]), Vec<usize>> = HashMap::new();
+
+    for (index, signature) in signatures.iter().enumerate() {
+        for (band, rows) in signature.chunks(ROWS_PER_BAND).enumerate() {
+            buckets.entry((band, rows)).or_default().push(index);
+        }
+    }
+
+    let candidates: HashSet<(usize, usize)> = buckets
+        .values()
+        .flat_map(|indexes| {
+            indexes
+                .iter()
+                .enumerate()
+                .flat_map(move |(position, &first)| {
+                    indexes[position + 1..]
+                        .iter()
+                        .map(move |&second| (first, second))
+                })
+        })
+        .collect();
+
+    let mut pairs: Vec<_> = candidates
+        .into_iter()
+        .filter_map(|(first, second)| {
+            let (first_question, first_shingles) = &questions[first];
+            let (second_question, second_shingles) = &questions[second];
+            let score = jaccard(first_shingles, second_shingles);
+
+            (score >= min_score).then_some(DuplicatePair {
+                first: first_question,
+                second: second_question,
+                score,
+            })
+        })
+        .collect();
+
+    pairs.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.first.id.cmp(&b.first.id))
+            .then_with(|| a.second.id.cmp(&b.second.id))
+    });
+
+    pairs
+}
+
+/// Lowercases the text, strips accents and turns punctuation into spaces.
+fn normalize(text: &str) -> String {
+    let folded: String = text
+        .nfd()
+        .filter(|character| !is_combining_mark(*character))
+        .flat_map(char::to_lowercase)
+        .map(|character| {
+            if character.is_alphanumeric() {
+                character
+            } else {
+                ' '
+            }
+        })
+        .collect();
+
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The hashed shingles of the question text and of each option on its own, so that the options
+/// can be in any order.
+fn shingles(question: &QuestionData) -> HashSet<u64> {
+    let mut shingles = HashSet::new();
+
+    for text in std::iter::once(&question.text)
+        .chain(question.question_options.iter().map(|option| &option.text))
+    {
+        let characters: Vec<_> = normalize(text).chars().collect();
+
+        if characters.is_empty() {
+            continue;
+        }
+
+        for window in characters.windows(SHINGLE_LENGTH.min(characters.len())) {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            shingles.insert(hasher.finish());
+        }
+    }
+
+    shingles
+}
+
+fn min_hash_signature(shingles: &HashSet<u64>) -> Vec<u64> {
+    (0..SIGNATURE_LENGTH as u64)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|shingle| mix(shingle ^ mix(seed)))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+/// The SplitMix64 finalizer, used as a cheap family of hash functions.
+fn mix(value: u64) -> u64 {
+    let mut value = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    value ^ (value >> 31)
+}
+
+fn jaccard(first: &HashSet<u64>, second: &HashSet<u64>) -> f64 {
+    let intersection = first.intersection(second).count();
+    let union = first.len() + second.len() - intersection;
+
+    intersection as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{course, question_with_options};
+
+    const TEXT: &str = "¿Cuál es la causa más frecuente de insuficiencia cardíaca en adultos?";
+    const OPTIONS: [&str; 2] = ["Hipertensión arterial", "Valvulopatía reumática"];
+
+    fn scores(first: serde_json::Value, second: serde_json::Value) -> Vec<f64> {
+        let courses_data = [course("cardio", vec![first]), course("spyh", vec![second])];
+
+        find_duplicates(&courses_data, 0.8)
+            .iter()
+            .map(|pair| pair.score)
+            .collect()
+    }
+
+    #[test]
+    fn finds_questions_differing_in_accents_punctuation_and_casing() {
+        let variant = question_with_options(
+            2,
+            "cual es la CAUSA mas frecuente de insuficiencia cardiaca, en adultos",
+            ["hipertension arterial.", "Valvulopatia (reumatica)"],
+        );
+
+        assert_eq!(
+            scores(question_with_options(1, TEXT, OPTIONS), variant),
+            [1.0]
+        );
+    }
+
+    #[test]
+    fn finds_questions_with_options_in_another_order() {
+        let variant = question_with_options(2, TEXT, [OPTIONS[1], OPTIONS[0]]);
+
+        assert_eq!(
+            scores(question_with_options(1, TEXT, OPTIONS), variant),
+            [1.0]
+        );
+    }
+
+    #[test]
+    fn finds_slightly_edited_questions() {
+        let variant = question_with_options(
+            2,
+            "¿Cuál es la causa más frecuente de insuficiencia cardíaca en los adultos?",
+            OPTIONS,
+        );
+        let scores = scores(question_with_options(1, TEXT, OPTIONS), variant);
+
+        assert_eq!(scores.len(), 1);
+        assert!(scores[0] >= 0.8 && scores[0] < 1.0, "{scores:?}");
+    }
+
+    #[test]
+    fn ignores_unrelated_questions() {
+        let unrelated = question_with_options(
+            2,
+            "Con respecto a la circulación cerebral, marque la opción correcta:",
+            [
+                "La autorregulación mantiene el flujo",
+                "El flujo depende de la presión venosa",
+            ],
+        );
+
+        assert!(scores(question_with_options(1, TEXT, OPTIONS), unrelated).is_empty());
+    }
+
+    #[test]
+    fn sorts_pairs_by_score() {
+        let courses_data = [course(
+            "cardio",
+            vec![
+                question_with_options(1, TEXT, OPTIONS),
+                question_with_options(
+                    2,
+                    "¿Cuál es la causa más frecuente de insuficiencia cardíaca en los adultos?",
+                    OPTIONS,
+                ),
+                question_with_options(3, TEXT, [OPTIONS[1], OPTIONS[0]]),
+            ],
+        )];
+
+        let pairs = find_duplicates(&courses_data, 0.8);
+        let scores: Vec<_> = pairs.iter().map(|pair| pair.score).collect();
+
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(scores[0], 1.0);
+        assert!(scores.windows(2).all(|window| window[0] >= window[1]));
+    }
+}
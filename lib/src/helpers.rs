@@ -1,11 +1,12 @@
+use std::env;
 use std::fs::{self, DirEntry, ReadDir};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-use crate::CourseData;
+use crate::{CourseData, Diagnostics, ImageRenaming};
 
 static WHITESPACE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s\s+").unwrap());
 
@@ -30,20 +31,58 @@ pub fn write_data(path: PathBuf, data: String) -> Result<()> {
     Ok(())
 }
 
+/// Loads and formats every course data file, skipping the ones with errors. Check `diagnostics`
+/// before using the result, as skipped courses are missing from it.
 pub async fn load_courses_data_and_write_formatted(
     data_path: PathBuf,
     images_path: PathBuf,
+    diagnostics: &mut Diagnostics,
 ) -> Result<Vec<CourseData>> {
     let mut courses_data = vec![];
 
     for dir_entry in read_data_dir(data_path)? {
-        courses_data
-            .push(CourseData::load_and_write_formatted(dir_entry?, images_path.clone()).await?);
+        courses_data.extend(
+            CourseData::load_and_write_formatted(dir_entry?, images_path.clone(), diagnostics)
+                .await?,
+        );
     }
 
     Ok(courses_data)
 }
 
+/// Loads every course data file through the formatting pipeline without writing anything back,
+/// skipping the ones with errors like [`load_courses_data_and_write_formatted`].
+pub async fn load_courses_data(
+    data_path: PathBuf,
+    images_path: PathBuf,
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<CourseData>> {
+    let mut courses_data = vec![];
+
+    for dir_entry in read_data_dir(data_path)? {
+        let formatted = CourseData::load_formatted(
+            dir_entry?,
+            images_path.clone(),
+            ImageRenaming::Plan,
+            diagnostics,
+        )
+        .await?;
+
+        courses_data.extend(formatted.map(|formatted| formatted.data));
+    }
+
+    Ok(courses_data)
+}
+
+/// The path relative to the current directory when possible, as users usually run the CLI from
+/// the repository root.
+pub fn display_path(path: &Path) -> PathBuf {
+    env::current_dir()
+        .ok()
+        .and_then(|current_dir| path.strip_prefix(current_dir).ok().map(Path::to_owned))
+        .unwrap_or_else(|| path.to_owned())
+}
+
 pub fn format_text(text: &str) -> String {
     let mut formatted = text.trim().to_owned();
 
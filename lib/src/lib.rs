@@ -1,11 +1,23 @@
 mod data;
+mod diagnostics;
+mod duplicates;
 mod helpers;
+mod plan;
 mod raw_data;
+mod source_map;
 mod sync;
+/// Course data fixtures for tests, also available to dependent crates through the `test-data`
+/// feature.
+#[cfg(any(test, feature = "test-data"))]
+pub mod test_data;
 mod traits;
 
 pub use data::*;
+pub use diagnostics::*;
+pub use duplicates::*;
 pub use helpers::*;
+pub use plan::*;
 pub use raw_data::*;
+pub use source_map::*;
 pub use sync::*;
 pub use traits::*;
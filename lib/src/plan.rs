@@ -0,0 +1,620 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    CourseData, CourseEvaluationData, CourseTopicData, ImageData, QuestionData, QuestionOptionData,
+    SyncData, SyncMetadata,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EntityPlan<K, T> {
+    pub added: Vec<T>,
+    pub changed: Vec<T>,
+    pub deleted: Vec<K>,
+    pub unchanged: usize,
+}
+
+impl<K, T> EntityPlan<K, T> {
+    pub fn counts(&self) -> ChangeCounts {
+        ChangeCounts {
+            added: self.added.len(),
+            changed: self.changed.len(),
+            deleted: self.deleted.len(),
+            unchanged: self.unchanged,
+        }
+    }
+
+    fn push(&mut self, previous_hash: Option<String>, hash: &str, entity: T) {
+        match previous_hash {
+            Some(previous_hash) if previous_hash == hash => self.unchanged += 1,
+            Some(_) => self.changed.push(entity),
+            None => self.added.push(entity),
+        }
+    }
+
+    fn set_deleted<V>(&mut self, remaining_metadata: HashMap<K, V>)
+    where
+        K: Hash + Eq,
+    {
+        self.deleted = remaining_metadata.into_keys().collect();
+    }
+
+    fn into_sync(self) -> (Vec<T>, Vec<K>) {
+        let mut to_sync = self.added;
+        to_sync.extend(self.changed);
+
+        (to_sync, self.deleted)
+    }
+}
+
+impl<K, T> Default for EntityPlan<K, T> {
+    fn default() -> Self {
+        Self {
+            added: vec![],
+            changed: vec![],
+            deleted: vec![],
+            unchanged: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct ChangeCounts {
+    pub added: usize,
+    pub changed: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct SyncSummary {
+    pub courses: ChangeCounts,
+    pub course_evaluations: ChangeCounts,
+    pub course_topics: ChangeCounts,
+    pub questions: ChangeCounts,
+    pub question_options: ChangeCounts,
+    pub images: Option<ChangeCounts>,
+}
+
+/// The changes needed to bring the engine from `SyncMetadata` to the local course data.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SyncPlan {
+    pub courses: EntityPlan<String, CourseData>,
+    pub course_evaluations: EntityPlan<String, CourseEvaluationData>,
+    pub course_topics: EntityPlan<String, CourseTopicData>,
+    pub questions: EntityPlan<Uuid, QuestionData>,
+    pub question_options: EntityPlan<Uuid, QuestionOptionData>,
+    /// The images to sync, or `None` if the engine doesn't keep track of them.
+    pub images: Option<EntityPlan<String, ImageData>>,
+}
+
+impl SyncPlan {
+    /// Judges every course, evaluation, topic, question and option purely on its own hash, so a change
+    /// is never dropped because the entity containing it looks unchanged.
+    pub fn compute(courses_data: &[CourseData], mut sync_metadata: SyncMetadata) -> Self {
+        let mut plan = Self::default();
+
+        if let Some(mut images_metadata) = sync_metadata.images_metadata.take() {
+            let mut images_plan = EntityPlan::default();
+
+            for image_data in courses_data.iter().flat_map(CourseData::images) {
+                let hash = image_data.hash.clone();
+
+                images_plan.push(images_metadata.remove(&image_data.path), &hash, image_data);
+            }
+
+            images_plan.set_deleted(images_metadata);
+            plan.images = Some(images_plan);
+        }
+
+        for course_data in courses_data {
+            let mut course_data = course_data.clone();
+
+            for course_evaluation_data in course_data.evaluations.drain(..) {
+                let hash = course_evaluation_data.hash.clone();
+
+                plan.course_evaluations.push(
+                    sync_metadata
+                        .course_evaluations_metadata
+                        .remove(&course_evaluation_data.full_key()),
+                    &hash,
+                    course_evaluation_data,
+                );
+            }
+
+            for course_topic_data in course_data.topics.drain(..) {
+                let hash = course_topic_data.hash.clone();
+
+                plan.course_topics.push(
+                    sync_metadata
+                        .course_topics_metadata
+                        .remove(&course_topic_data.full_key()),
+                    &hash,
+                    course_topic_data,
+                );
+            }
+
+            for mut question_data in course_data.questions.drain(..) {
+                for question_option_data in question_data.question_options.drain(..) {
+                    let hash = question_option_data.hash.clone();
+
+                    plan.question_options.push(
+                        sync_metadata
+                            .question_options_metadata
+                            .remove(&question_option_data.id),
+                        &hash,
+                        question_option_data,
+                    );
+                }
+
+                let hash = question_data.hash.clone();
+
+                plan.questions.push(
+                    sync_metadata.questions_metadata.remove(&question_data.id),
+                    &hash,
+                    question_data,
+                );
+            }
+
+            let hash = course_data.hash.clone();
+
+            plan.courses.push(
+                sync_metadata.courses_metadata.remove(&course_data.key),
+                &hash,
+                course_data,
+            );
+        }
+
+        plan.courses.set_deleted(sync_metadata.courses_metadata);
+        plan.course_evaluations
+            .set_deleted(sync_metadata.course_evaluations_metadata);
+        plan.course_topics
+            .set_deleted(sync_metadata.course_topics_metadata);
+        plan.questions.set_deleted(sync_metadata.questions_metadata);
+        plan.question_options
+            .set_deleted(sync_metadata.question_options_metadata);
+
+        plan
+    }
+
+    /// Describes every deletion in the plan that goes beyond what a routine sync should do: removing
+    /// a whole course, or more than `max_deleted_questions_percent` of the questions in the engine.
+    pub fn excessive_deletions(&self, max_deleted_questions_percent: f64) -> Vec<String> {
+        let mut excessive_deletions: Vec<_> = self
+            .courses
+            .deleted
+            .iter()
+            .map(|course_key| format!("Course {course_key} would be deleted"))
+            .collect();
+
+        let questions = self.questions.counts();
+        let existing_questions = questions.deleted + questions.changed + questions.unchanged;
+
+        if existing_questions > 0 {
+            let deleted_percent = questions.deleted as f64 * 100.0 / existing_questions as f64;
+
+            if deleted_percent > max_deleted_questions_percent {
+                excessive_deletions.push(format!(
+                    "{} of {existing_questions} questions ({deleted_percent:.1}%) would be deleted, \
+                     above the {max_deleted_questions_percent}% limit",
+                    questions.deleted
+                ));
+            }
+        }
+
+        excessive_deletions
+    }
+
+    pub fn summary(&self) -> SyncSummary {
+        SyncSummary {
+            courses: self.courses.counts(),
+            course_evaluations: self.course_evaluations.counts(),
+            course_topics: self.course_topics.counts(),
+            questions: self.questions.counts(),
+            question_options: self.question_options.counts(),
+            images: self.images.as_ref().map(EntityPlan::counts),
+        }
+    }
+}
+
+impl From<SyncPlan> for SyncData {
+    fn from(plan: SyncPlan) -> Self {
+        let (courses_to_sync, courses_to_delete) = plan.courses.into_sync();
+        let (questions_to_sync, questions_to_delete) = plan.questions.into_sync();
+        let (question_options_to_sync, question_options_to_delete) =
+            plan.question_options.into_sync();
+        let (course_evaluations_to_sync, course_evaluations_to_delete) =
+            plan.course_evaluations.into_sync();
+        let (course_topics_to_sync, course_topics_to_delete) = plan.course_topics.into_sync();
+        let (images_to_sync, images_to_delete) = plan.images.unwrap_or_default().into_sync();
+
+        Self {
+            courses_to_sync,
+            courses_to_delete,
+
+            questions_to_sync,
+            questions_to_delete,
+
+            question_options_to_sync,
+            question_options_to_delete,
+
+            course_evaluations_to_sync,
+            course_evaluations_to_delete,
+
+            course_topics_to_sync,
+            course_topics_to_delete,
+
+            images_to_sync,
+            images_to_delete,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{
+        course, course_with_evaluations, question, question_with_options, synced,
+    };
+
+    fn counts(added: usize, changed: usize, deleted: usize, unchanged: usize) -> ChangeCounts {
+        ChangeCounts {
+            added,
+            changed,
+            deleted,
+            unchanged,
+        }
+    }
+
+    #[test]
+    fn summary_counts_every_entity_type() {
+        let before = course("cardio", vec![question(1, "One"), question(2, "Two")]);
+        let after = course("cardio", vec![question(1, "One"), question(3, "Three")]);
+
+        let summary = SyncPlan::compute(&[after], synced(&[before])).summary();
+
+        assert_eq!(
+            summary,
+            SyncSummary {
+                courses: counts(0, 1, 0, 0),
+                course_evaluations: counts(0, 0, 0, 1),
+                course_topics: counts(0, 0, 0, 1),
+                questions: counts(1, 0, 1, 1),
+                question_options: counts(2, 0, 2, 2),
+                images: None,
+            }
+        );
+    }
+
+    #[test]
+    fn sync_data_has_added_and_changed_entities_and_deleted_keys() {
+        let before = course("cardio", vec![question(1, "One"), question(2, "Two")]);
+        let after = course(
+            "cardio",
+            vec![question(1, "One, edited"), question(3, "Three")],
+        );
+
+        let sync_data: SyncData = SyncPlan::compute(&[after], synced(&[before])).into();
+
+        let mut synced_question_ids: Vec<_> = sync_data
+            .questions_to_sync
+            .iter()
+            .map(|question| question.id)
+            .collect();
+        synced_question_ids.sort();
+
+        assert_eq!(
+            synced_question_ids,
+            [Uuid::from_u128(1), Uuid::from_u128(3)]
+        );
+        assert_eq!(sync_data.questions_to_delete, [Uuid::from_u128(2)]);
+        assert_eq!(sync_data.courses_to_sync.len(), 1);
+        assert!(sync_data.courses_to_delete.is_empty());
+        assert!(sync_data.course_evaluations_to_sync.is_empty());
+        assert_eq!(sync_data.question_options_to_sync.len(), 2);
+        assert_eq!(sync_data.question_options_to_delete.len(), 2);
+    }
+
+    #[test]
+    fn excessive_deletions_reports_deleted_courses() {
+        let cardio = course("cardio", vec![question(1, "One")]);
+        let spyh = course("spyh", vec![question(2, "Two")]);
+
+        let sync_metadata = synced(&[cardio.clone(), spyh]);
+        let plan = SyncPlan::compute(&[cardio], sync_metadata);
+
+        assert_eq!(
+            plan.excessive_deletions(100.0),
+            ["Course spyh would be deleted"]
+        );
+    }
+
+    #[test]
+    fn excessive_deletions_compares_deleted_questions_to_the_engine_questions() {
+        // Synced questions, questions kept, questions added, limit, whether it's excessive.
+        let cases = [
+            (10, 10, 0, 10.0, false),
+            (10, 9, 0, 10.0, false),
+            (10, 8, 0, 10.0, true),
+            (10, 9, 0, 9.9, true),
+            // Added questions aren't in the engine yet, so they don't dilute the deletions.
+            (10, 9, 5, 9.0, true),
+            (10, 0, 0, 100.0, false),
+            (0, 0, 5, 0.0, false),
+        ];
+
+        for (synced_count, kept_count, added_count, limit, is_excessive) in cases {
+            let before = course(
+                "cardio",
+                (0..synced_count)
+                    .map(|id| question(id, &format!("Question {id}")))
+                    .collect(),
+            );
+            let after = course(
+                "cardio",
+                (0..kept_count)
+                    .chain(100..100 + added_count)
+                    .map(|id| question(id, &format!("Question {id}")))
+                    .collect(),
+            );
+
+            let excessive_deletions =
+                SyncPlan::compute(&[after], synced(&[before])).excessive_deletions(limit);
+
+            assert_eq!(
+                !excessive_deletions.is_empty(),
+                is_excessive,
+                "{synced_count} synced, {kept_count} kept, {added_count} added, {limit}% limit: \
+                 {excessive_deletions:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn compute_judges_each_entity_on_its_own_hash() {
+        let one_evaluation = [("parcial_1", "Parcial 1")];
+        let two_evaluations = [("parcial_1", "Parcial 1"), ("parcial_2", "Parcial 2")];
+
+        // Synced courses, local courses, and the expected counts of courses, evaluations,
+        // questions and question options.
+        let cases = [
+            (
+                "nothing synced yet",
+                vec![],
+                vec![course("cardio", vec![question(1, "One")])],
+                [
+                    counts(1, 0, 0, 0),
+                    counts(1, 0, 0, 0),
+                    counts(1, 0, 0, 0),
+                    counts(2, 0, 0, 0),
+                ],
+            ),
+            (
+                "nothing changed",
+                vec![course("cardio", vec![question(1, "One")])],
+                vec![course("cardio", vec![question(1, "One")])],
+                [
+                    counts(0, 0, 0, 1),
+                    counts(0, 0, 0, 1),
+                    counts(0, 0, 0, 1),
+                    counts(0, 0, 0, 2),
+                ],
+            ),
+            (
+                "question added",
+                vec![course("cardio", vec![question(1, "One")])],
+                vec![course(
+                    "cardio",
+                    vec![question(1, "One"), question(2, "Two")],
+                )],
+                [
+                    counts(0, 1, 0, 0),
+                    counts(0, 0, 0, 1),
+                    counts(1, 0, 0, 1),
+                    counts(2, 0, 0, 2),
+                ],
+            ),
+            (
+                "question text changed",
+                vec![course("cardio", vec![question(1, "One")])],
+                vec![course("cardio", vec![question(1, "One, edited")])],
+                [
+                    counts(0, 1, 0, 0),
+                    counts(0, 0, 0, 1),
+                    counts(0, 1, 0, 0),
+                    counts(0, 0, 0, 2),
+                ],
+            ),
+            (
+                "question deleted",
+                vec![course(
+                    "cardio",
+                    vec![question(1, "One"), question(2, "Two")],
+                )],
+                vec![course("cardio", vec![question(1, "One")])],
+                [
+                    counts(0, 1, 0, 0),
+                    counts(0, 0, 0, 1),
+                    counts(0, 0, 1, 1),
+                    counts(0, 0, 2, 2),
+                ],
+            ),
+            (
+                "option changed",
+                vec![course("cardio", vec![question(1, "One")])],
+                vec![course(
+                    "cardio",
+                    vec![question_with_options(
+                        1,
+                        "One",
+                        ["Correct", "Wrong, edited"],
+                    )],
+                )],
+                [
+                    counts(0, 1, 0, 0),
+                    counts(0, 0, 0, 1),
+                    counts(0, 1, 0, 0),
+                    counts(0, 1, 0, 1),
+                ],
+            ),
+            (
+                "evaluation renamed",
+                vec![course("cardio", vec![question(1, "One")])],
+                vec![course_with_evaluations(
+                    "cardio",
+                    &[("parcial_1", "Primer parcial")],
+                    vec![question(1, "One")],
+                )],
+                [
+                    counts(0, 1, 0, 0),
+                    counts(0, 1, 0, 0),
+                    counts(0, 0, 0, 1),
+                    counts(0, 0, 0, 2),
+                ],
+            ),
+            (
+                "evaluation added",
+                vec![course_with_evaluations("cardio", &one_evaluation, vec![])],
+                vec![course_with_evaluations("cardio", &two_evaluations, vec![])],
+                [
+                    counts(0, 1, 0, 0),
+                    counts(1, 0, 0, 1),
+                    counts(0, 0, 0, 0),
+                    counts(0, 0, 0, 0),
+                ],
+            ),
+            (
+                "evaluation deleted",
+                vec![course_with_evaluations("cardio", &two_evaluations, vec![])],
+                vec![course_with_evaluations("cardio", &one_evaluation, vec![])],
+                [
+                    counts(0, 1, 0, 0),
+                    counts(0, 0, 1, 1),
+                    counts(0, 0, 0, 0),
+                    counts(0, 0, 0, 0),
+                ],
+            ),
+            (
+                "course added next to an unchanged one",
+                vec![course("cardio", vec![question(1, "One")])],
+                vec![
+                    course("cardio", vec![question(1, "One")]),
+                    course("spyh", vec![question(2, "Two")]),
+                ],
+                [
+                    counts(1, 0, 0, 1),
+                    counts(1, 0, 0, 1),
+                    counts(1, 0, 0, 1),
+                    counts(2, 0, 0, 2),
+                ],
+            ),
+            (
+                "course deleted",
+                vec![
+                    course("cardio", vec![question(1, "One")]),
+                    course("spyh", vec![question(2, "Two")]),
+                ],
+                vec![course("cardio", vec![question(1, "One")])],
+                [
+                    counts(0, 0, 1, 1),
+                    counts(0, 0, 1, 1),
+                    counts(0, 0, 1, 1),
+                    counts(0, 0, 2, 2),
+                ],
+            ),
+        ];
+
+        for (name, synced_courses, local_courses, expected) in cases {
+            let summary = SyncPlan::compute(&local_courses, synced(&synced_courses)).summary();
+
+            assert_eq!(
+                [
+                    summary.courses,
+                    summary.course_evaluations,
+                    summary.questions,
+                    summary.question_options,
+                ],
+                expected,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn compute_adds_new_questions_of_courses_whose_hash_matches() {
+        // The engine may have the current course hash without some of its questions, as when a
+        // previous sync failed halfway. Those questions used to be skipped along with the course.
+        let course_data = course("cardio", vec![question(1, "One"), question(2, "Two")]);
+        let mut sync_metadata = synced(std::slice::from_ref(&course_data));
+
+        sync_metadata.questions_metadata.remove(&Uuid::from_u128(2));
+        sync_metadata
+            .question_options_metadata
+            .retain(|id, _| ![Uuid::from_u128(21), Uuid::from_u128(22)].contains(id));
+
+        let plan = SyncPlan::compute(&[course_data], sync_metadata);
+
+        assert_eq!(plan.courses.unchanged, 1);
+        assert_eq!(plan.questions.unchanged, 1);
+        assert_eq!(
+            plan.questions
+                .added
+                .iter()
+                .map(|question| question.id)
+                .collect::<Vec<_>>(),
+            [Uuid::from_u128(2)]
+        );
+        assert_eq!(plan.question_options.added.len(), 2);
+    }
+
+    fn with_image(mut course_data: CourseData, image_hash: &str) -> CourseData {
+        course_data.image_file_name = Some(format!("{}.webp", course_data.key).into());
+        course_data.image_hash = Some(image_hash.to_owned());
+
+        course_data
+    }
+
+    #[test]
+    fn compute_leaves_images_out_when_the_engine_doesnt_track_them() {
+        let course_data = with_image(course("cardio", vec![]), "hash");
+
+        let plan = SyncPlan::compute(&[course_data], SyncMetadata::default());
+
+        assert!(plan.images.is_none());
+        assert_eq!(plan.summary().images, None);
+        assert!(SyncData::from(plan).images_to_sync.is_empty());
+    }
+
+    #[test]
+    fn compute_diffs_the_images_tracked_by_the_engine() {
+        let courses_data = [
+            with_image(course("cardio", vec![]), "new hash"),
+            with_image(course("spyh", vec![]), "hash"),
+            course("neuro", vec![]),
+        ];
+        let sync_metadata = SyncMetadata {
+            images_metadata: Some(
+                [
+                    ("cardio/cardio.webp", "old hash"),
+                    ("spyh/spyh.webp", "hash"),
+                    ("neuro/neuro.webp", "hash"),
+                ]
+                .into_iter()
+                .map(|(path, hash)| (path.to_owned(), hash.to_owned()))
+                .collect(),
+            ),
+            ..Default::default()
+        };
+
+        let images = SyncPlan::compute(&courses_data, sync_metadata)
+            .images
+            .unwrap();
+
+        assert_eq!(images.counts(), counts(0, 1, 1, 1));
+        assert_eq!(images.changed[0].path, "cardio/cardio.webp");
+        assert_eq!(images.deleted, ["neuro/neuro.webp"]);
+    }
+}
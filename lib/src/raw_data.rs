@@ -2,12 +2,17 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 use chrono::NaiveDate;
+use schemars::{schema::RootSchema, JsonSchema};
 use serde::{Deserialize, Serialize};
+use url::Url;
 use uuid::Uuid;
 
-use crate::{CourseData, CourseEvaluationData, QuestionData, QuestionOptionData};
+use crate::{
+    CourseData, CourseEvaluationData, CourseTopicData, QuestionData, QuestionKind,
+    QuestionOptionData, QuestionSource, ReferenceData,
+};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct RawCourseData {
     pub name: String,
@@ -20,11 +25,18 @@ pub struct RawCourseData {
     pub order: Option<i16>,
     pub questions: Vec<RawQuestionData>,
     pub evaluations: Vec<RawCourseEvaluationData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<RawCourseTopicData>,
 }
 
 impl RawCourseData {
     pub fn from_slice(raw_data: &[u8]) -> Result<Self> {
-        Ok(serde_json::from_slice(&raw_data)?)
+        Ok(serde_json::from_slice(raw_data)?)
+    }
+
+    /// The JSON Schema of course data files, for editor validation and completion.
+    pub fn json_schema() -> RootSchema {
+        schemars::schema_for!(Self)
     }
 }
 
@@ -32,6 +44,7 @@ impl From<CourseData> for RawCourseData {
     fn from(data: CourseData) -> Self {
         let raw_questions = data.questions.into_iter().map(Into::into).collect();
         let raw_evaluations = data.evaluations.into_iter().map(Into::into).collect();
+        let raw_topics = RawCourseTopicData::tree(&data.topics, None);
 
         Self {
             name: data.name,
@@ -42,23 +55,33 @@ impl From<CourseData> for RawCourseData {
             order: data.order,
             questions: raw_questions,
             evaluations: raw_evaluations,
+            topics: raw_topics,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct RawQuestionData {
     pub id: Option<Uuid>,
 
+    #[serde(default, skip_serializing_if = "QuestionKind::is_single_choice")]
+    pub kind: QuestionKind,
     pub evaluation: String,
-    pub source: String,
+    pub source: QuestionSource,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub asked_at: Option<NaiveDate>,
     pub text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image: Option<PathBuf>,
+    #[schemars(length(min = 2, max = 5))]
     pub options: Vec<RawQuestionOptionData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<RawReferenceData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<String>,
 }
 
 impl From<QuestionData> for RawQuestionData {
@@ -67,17 +90,45 @@ impl From<QuestionData> for RawQuestionData {
 
         Self {
             id: Some(data.id),
+            kind: data.kind,
             text: data.text,
             image: data.image_file_name,
             options: raw_question_options,
             evaluation: data.evaluation,
             asked_at: data.asked_at,
             source: data.source,
+            explanation: data.explanation,
+            references: data.references.into_iter().map(Into::into).collect(),
+            topics: data.topics,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawReferenceData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub book: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chapter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<Url>,
+}
+
+impl From<ReferenceData> for RawReferenceData {
+    fn from(data: ReferenceData) -> Self {
+        Self {
+            book: data.book,
+            chapter: data.chapter,
+            page: data.page,
+            url: data.url,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct RawQuestionOptionData {
     pub id: Option<Uuid>,
 
@@ -103,7 +154,7 @@ impl From<QuestionOptionData> for RawQuestionOptionData {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct RawCourseEvaluationData {
     pub key: String,
     pub name: String,
@@ -117,3 +168,27 @@ impl From<CourseEvaluationData> for RawCourseEvaluationData {
         }
     }
 }
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RawCourseTopicData {
+    pub key: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtopics: Vec<RawCourseTopicData>,
+}
+
+impl RawCourseTopicData {
+    /// Rebuilds the children of the topic keyed `parent_key` from the flattened topics.
+    fn tree(topics: &[CourseTopicData], parent_key: Option<&str>) -> Vec<Self> {
+        topics
+            .iter()
+            .filter(|topic| topic.parent_key.as_deref() == parent_key)
+            .map(|topic| Self {
+                key: topic.key.clone(),
+                name: topic.name.clone(),
+                subtopics: Self::tree(topics, Some(&topic.key)),
+            })
+            .collect()
+    }
+}
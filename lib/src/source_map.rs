@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// A position in a source file, with 1-based line and column numbers counted in characters.
+#[derive(Serialize, PartialEq, Eq, Clone, Debug)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String,
+}
+
+/// Maps the JSON pointer of every value in a JSON document to where the value starts, so
+/// problems found after deserializing can point back at the source.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    source: String,
+    line_starts: Vec<usize>,
+    value_offsets: HashMap<String, usize>,
+}
+
+impl SourceMap {
+    pub fn new(source: &[u8]) -> Self {
+        let mut source_map = Self::lines(source);
+
+        let mut scanner = Scanner {
+            source: source_map.source.as_bytes(),
+            offset: 0,
+            value_offsets: HashMap::new(),
+        };
+        scanner.scan_value(String::new());
+
+        source_map.value_offsets = scanner.value_offsets;
+
+        source_map
+    }
+
+    /// Maps only the lines of `source`, for locating positions by line in files that aren't JSON.
+    pub fn lines(source: &[u8]) -> Self {
+        let source = String::from_utf8_lossy(source).into_owned();
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(offset, _)| offset + 1))
+            .collect();
+
+        Self {
+            source,
+            line_starts,
+            value_offsets: HashMap::new(),
+        }
+    }
+
+    /// Locates the value at `pointer`, such as `/questions/3/options`.
+    pub fn locate(&self, pointer: &str) -> Option<SourceLocation> {
+        self.location_at_offset(*self.value_offsets.get(pointer)?)
+    }
+
+    /// Locates a position given as a 1-based line and a 1-based byte column, as reported by
+    /// `serde_json`.
+    pub fn locate_line_byte_column(
+        &self,
+        line: usize,
+        byte_column: usize,
+    ) -> Option<SourceLocation> {
+        let line_start = *self.line_starts.get(line.checked_sub(1)?)?;
+
+        self.location_at_offset(line_start + byte_column.saturating_sub(1))
+    }
+
+    fn location_at_offset(&self, offset: usize) -> Option<SourceLocation> {
+        let offset = offset.min(self.source.len());
+        let line_index = match self.line_starts.binary_search(&offset) {
+            Ok(line_index) => line_index,
+            Err(next_line_index) => next_line_index - 1,
+        };
+        let line_start = self.line_starts[line_index];
+        let line_text = self.source[line_start..]
+            .split('\n')
+            .next()
+            .unwrap_or_default();
+        let column_text = self.source.get(line_start..offset)?;
+
+        Some(SourceLocation {
+            line: line_index + 1,
+            column: column_text.chars().count() + 1,
+            snippet: line_text.trim_end_matches('\r').to_owned(),
+        })
+    }
+}
+
+/// A lenient JSON scanner that only records where values start. It relies on the document having
+/// been validated by `serde_json`, and simply stops on anything unexpected.
+struct Scanner<'a> {
+    source: &'a [u8],
+    offset: usize,
+    value_offsets: HashMap<String, usize>,
+}
+
+impl Scanner<'_> {
+    fn scan_value(&mut self, pointer: String) -> Option<()> {
+        self.skip_whitespace();
+        self.value_offsets.insert(pointer.clone(), self.offset);
+
+        match self.peek()? {
+            b'{' => self.scan_object(pointer),
+            b'[' => self.scan_array(pointer),
+            b'"' => self.scan_string().map(drop),
+            _ => {
+                while !matches!(self.peek()?, b',' | b'}' | b']')
+                    && !self.peek()?.is_ascii_whitespace()
+                {
+                    self.offset += 1;
+                }
+
+                Some(())
+            }
+        }
+    }
+
+    fn scan_object(&mut self, pointer: String) -> Option<()> {
+        self.offset += 1;
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek()? {
+                b'}' => {
+                    self.offset += 1;
+                    return Some(());
+                }
+                b',' => self.offset += 1,
+                b'"' => {
+                    let key = self.scan_string()?;
+
+                    self.skip_whitespace();
+                    self.expect(b':')?;
+                    self.scan_value(format!("{pointer}/{}", escape_pointer_token(&key)))?;
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn scan_array(&mut self, pointer: String) -> Option<()> {
+        self.offset += 1;
+
+        let mut index = 0;
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek()? {
+                b']' => {
+                    self.offset += 1;
+                    return Some(());
+                }
+                b',' => self.offset += 1,
+                _ => {
+                    self.scan_value(format!("{pointer}/{index}"))?;
+                    index += 1;
+                }
+            }
+        }
+    }
+
+    fn scan_string(&mut self) -> Option<String> {
+        self.expect(b'"')?;
+
+        let start = self.offset;
+
+        loop {
+            match self.peek()? {
+                b'\\' => self.offset += 2,
+                b'"' => break,
+                _ => self.offset += 1,
+            }
+        }
+
+        let raw = std::str::from_utf8(self.source.get(start..self.offset)?).ok()?;
+        self.offset += 1;
+
+        serde_json::from_str(&format!("\"{raw}\"")).ok()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(byte) if byte.is_ascii_whitespace()) {
+            self.offset += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        if self.peek()? == byte {
+            self.offset += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.get(self.offset).copied()
+    }
+}
+
+fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The line, column and source text from the located position onwards.
+    fn located(location: Option<SourceLocation>) -> (usize, usize, String) {
+        let location = location.unwrap();
+        let rest = location.snippet.chars().skip(location.column - 1).collect();
+
+        (location.line, location.column, rest)
+    }
+
+    #[test]
+    fn locates_nested_values() {
+        let source_map = SourceMap::new(
+            br#"{
+  "questions": [
+    {
+      "options": [
+        { "text": "a" },
+        { "text": "b", "correct": true }
+      ]
+    }
+  ]
+}"#,
+        );
+
+        assert_eq!(located(source_map.locate("")), (1, 1, "{".to_owned()));
+        assert_eq!(
+            located(source_map.locate("/questions/0")),
+            (3, 5, "{".to_owned())
+        );
+        assert_eq!(
+            located(source_map.locate("/questions/0/options/1/text")),
+            (6, 19, r#""b", "correct": true }"#.to_owned())
+        );
+        assert_eq!(
+            located(source_map.locate("/questions/0/options/1/correct")),
+            (6, 35, "true }".to_owned())
+        );
+        assert_eq!(source_map.locate("/questions/1"), None);
+    }
+
+    #[test]
+    fn escapes_keys_in_pointers() {
+        let source_map = SourceMap::new(br#"{"a/b": 1, "c~d": {"~/": 2}}"#);
+
+        assert_eq!(
+            located(source_map.locate("/a~1b")).2,
+            "1, \"c~d\": {\"~/\": 2}}"
+        );
+        assert_eq!(located(source_map.locate("/c~0d/~0~1")).2, "2}}");
+        assert_eq!(source_map.locate("/a/b"), None);
+    }
+
+    #[test]
+    fn skips_escaped_quotes_in_strings() {
+        let source_map =
+            SourceMap::new(br#"{"text": "say \"hi\", {\"a\": [", "next\"": [3, "\\"], "last": 4}"#);
+
+        assert_eq!(
+            located(source_map.locate("/next\"/0")).2,
+            "3, \"\\\\\"], \"last\": 4}"
+        );
+        assert_eq!(
+            located(source_map.locate("/next\"/1")).2,
+            "\"\\\\\"], \"last\": 4}"
+        );
+        assert_eq!(located(source_map.locate("/last")).2, "4}");
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        let source_map = SourceMap::new("{\n  \"texto\": \"cardíaco ❤\", \"b\": 1\n}".as_bytes());
+
+        assert_eq!(located(source_map.locate("/b")), (2, 31, "1".to_owned()));
+    }
+
+    #[test]
+    fn locates_serde_errors_by_line_and_byte_column() {
+        let source = "{\n  \"texto\": \"cardíaco ❤\" x\n}";
+        let error = serde_json::from_str::<serde_json::Value>(source).unwrap_err();
+
+        let (line, column, rest) = located(
+            SourceMap::new(source.as_bytes()).locate_line_byte_column(error.line(), error.column()),
+        );
+
+        assert_eq!((line, column, rest.as_str()), (2, 25, "x"));
+        assert!(error.column() > column);
+    }
+
+    #[test]
+    fn lines_locates_by_line_without_scanning_values() {
+        let source_map = SourceMap::lines(b"text,a,b\n\"Qu\xc3\xa9?\",S\xc3\xad,No\n");
+
+        assert_eq!(source_map.locate(""), None);
+        assert_eq!(
+            located(source_map.locate_line_byte_column(2, 9)),
+            (2, 8, "S\u{ed},No".to_owned())
+        );
+    }
+}
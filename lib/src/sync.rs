@@ -3,7 +3,9 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::{CourseData, CourseEvaluationData, QuestionData, QuestionOptionData};
+use super::{
+    CourseData, CourseEvaluationData, CourseTopicData, ImageData, QuestionData, QuestionOptionData,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SyncData {
@@ -18,13 +20,160 @@ pub struct SyncData {
 
     pub course_evaluations_to_sync: Vec<CourseEvaluationData>,
     pub course_evaluations_to_delete: Vec<String>,
+
+    pub course_topics_to_sync: Vec<CourseTopicData>,
+    pub course_topics_to_delete: Vec<String>,
+
+    pub images_to_sync: Vec<ImageData>,
+    pub images_to_delete: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct SyncMetadata {
     pub courses_metadata: HashMap<String, String>,
     pub questions_metadata: HashMap<Uuid, String>,
     pub question_options_metadata: HashMap<Uuid, String>,
     pub course_evaluations_metadata: HashMap<String, String>,
+    #[serde(default)]
+    pub course_topics_metadata: HashMap<String, String>,
+    /// The hash of every image the engine has synced, or `None` if it doesn't keep track of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images_metadata: Option<HashMap<String, String>>,
     pub images_bucket_name: String,
 }
+
+impl SyncMetadata {
+    /// Updates the metadata to reflect the engine state after `data` has been synced.
+    pub fn apply(&mut self, data: &SyncData) {
+        for course in &data.courses_to_sync {
+            self.courses_metadata
+                .insert(course.key.clone(), course.hash.clone());
+        }
+
+        for course_key in &data.courses_to_delete {
+            self.courses_metadata.remove(course_key);
+        }
+
+        for question in &data.questions_to_sync {
+            self.questions_metadata
+                .insert(question.id, question.hash.clone());
+        }
+
+        for question_id in &data.questions_to_delete {
+            self.questions_metadata.remove(question_id);
+        }
+
+        for question_option in &data.question_options_to_sync {
+            self.question_options_metadata
+                .insert(question_option.id, question_option.hash.clone());
+        }
+
+        for question_option_id in &data.question_options_to_delete {
+            self.question_options_metadata.remove(question_option_id);
+        }
+
+        for course_evaluation in &data.course_evaluations_to_sync {
+            self.course_evaluations_metadata
+                .insert(course_evaluation.full_key(), course_evaluation.hash.clone());
+        }
+
+        for course_evaluation_key in &data.course_evaluations_to_delete {
+            self.course_evaluations_metadata
+                .remove(course_evaluation_key);
+        }
+
+        for course_topic in &data.course_topics_to_sync {
+            self.course_topics_metadata
+                .insert(course_topic.full_key(), course_topic.hash.clone());
+        }
+
+        for course_topic_key in &data.course_topics_to_delete {
+            self.course_topics_metadata.remove(course_topic_key);
+        }
+
+        if !data.images_to_sync.is_empty() {
+            let images_metadata = self.images_metadata.get_or_insert_with(Default::default);
+
+            for image in &data.images_to_sync {
+                images_metadata.insert(image.path.clone(), image.hash.clone());
+            }
+        }
+
+        if let Some(images_metadata) = &mut self.images_metadata {
+            for image_path in &data.images_to_delete {
+                images_metadata.remove(image_path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{course, question, synced};
+    use crate::SyncPlan;
+
+    #[test]
+    fn applying_the_synced_data_leaves_nothing_to_sync() {
+        let local_courses = [
+            course(
+                "cardio",
+                vec![question(1, "One"), question(2, "Two, edited")],
+            ),
+            course("spyh", vec![question(4, "Four")]),
+        ];
+        let synced_courses = [
+            course("cardio", vec![question(2, "Two"), question(3, "Three")]),
+            course("neuro", vec![question(5, "Five")]),
+        ];
+
+        for mut sync_metadata in [SyncMetadata::default(), synced(&synced_courses)] {
+            let data = SyncPlan::compute(&local_courses, sync_metadata.clone()).into();
+
+            sync_metadata.apply(&data);
+
+            let summary = SyncPlan::compute(&local_courses, sync_metadata).summary();
+
+            for counts in [
+                summary.courses,
+                summary.course_evaluations,
+                summary.course_topics,
+                summary.questions,
+                summary.question_options,
+            ] {
+                assert_eq!(
+                    counts.added + counts.changed + counts.deleted,
+                    0,
+                    "{summary:?}"
+                );
+            }
+
+            assert_eq!(summary.courses.unchanged, 2);
+            assert_eq!(summary.questions.unchanged, 3);
+            assert_eq!(summary.question_options.unchanged, 6);
+        }
+    }
+
+    #[test]
+    fn applying_synced_images_starts_tracking_them() {
+        let image = ImageData {
+            path: "cardio/cardio.webp".to_owned(),
+            hash: "hash".to_owned(),
+        };
+        let mut data: SyncData = SyncPlan::default().into();
+        let mut sync_metadata = SyncMetadata::default();
+
+        sync_metadata.apply(&data);
+        assert_eq!(sync_metadata.images_metadata, None);
+
+        data.images_to_sync.push(image);
+        sync_metadata.apply(&data);
+        assert_eq!(
+            sync_metadata.images_metadata,
+            Some(HashMap::from([(
+                "cardio/cardio.webp".to_owned(),
+                "hash".to_owned()
+            )]))
+        );
+    }
+}
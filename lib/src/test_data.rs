@@ -0,0 +1,113 @@
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{CourseData, SyncMetadata};
+
+pub fn option(id: u128, text: &str, correct: bool) -> serde_json::Value {
+    json!({ "id": Uuid::from_u128(id), "text": text, "correct": correct })
+}
+
+pub fn question(id: u128, text: &str) -> serde_json::Value {
+    question_with_options(id, text, ["Correct", "Wrong"])
+}
+
+pub fn question_with_options(id: u128, text: &str, options: [&str; 2]) -> serde_json::Value {
+    json!({
+        "id": Uuid::from_u128(id),
+        "evaluation": "parcial_1",
+        "source": "partial",
+        "text": text,
+        "options": [
+            option(id * 10 + 1, options[0], true),
+            option(id * 10 + 2, options[1], false),
+        ],
+    })
+}
+
+/// A question of the given kind whose options are in the given order.
+pub fn question_with_kind(
+    id: u128,
+    kind: &str,
+    text: &str,
+    options: &[(&str, bool)],
+) -> serde_json::Value {
+    let options: Vec<_> = options
+        .iter()
+        .zip(1..)
+        .map(|((text, correct), position)| option(id * 10 + position, text, *correct))
+        .collect();
+
+    json!({
+        "id": Uuid::from_u128(id),
+        "kind": kind,
+        "evaluation": "parcial_1",
+        "source": "partial",
+        "text": text,
+        "options": options,
+    })
+}
+
+pub fn course(key: &str, questions: Vec<serde_json::Value>) -> CourseData {
+    course_with_evaluations(key, &[("parcial_1", "Parcial 1")], questions)
+}
+
+pub fn course_with_evaluations(
+    key: &str,
+    evaluations: &[(&str, &str)],
+    questions: Vec<serde_json::Value>,
+) -> CourseData {
+    let evaluations: Vec<_> = evaluations
+        .iter()
+        .map(|(key, name)| json!({ "key": key, "name": name }))
+        .collect();
+
+    CourseData::from_json(
+        key,
+        json!({
+            "name": key,
+            "short_name": key,
+            "aliases": [],
+            "year": 1,
+            "questions": questions,
+            "evaluations": evaluations,
+            "topics": [{ "key": "cardio", "name": "Cardio" }],
+        }),
+    )
+}
+
+/// The metadata of an engine that has synced exactly `courses_data`.
+pub fn synced(courses_data: &[CourseData]) -> SyncMetadata {
+    let mut sync_metadata = SyncMetadata::default();
+
+    for course_data in courses_data {
+        sync_metadata
+            .courses_metadata
+            .insert(course_data.key.clone(), course_data.hash.clone());
+
+        for evaluation in &course_data.evaluations {
+            sync_metadata
+                .course_evaluations_metadata
+                .insert(evaluation.full_key(), evaluation.hash.clone());
+        }
+
+        for topic in &course_data.topics {
+            sync_metadata
+                .course_topics_metadata
+                .insert(topic.full_key(), topic.hash.clone());
+        }
+
+        for question in &course_data.questions {
+            sync_metadata
+                .questions_metadata
+                .insert(question.id, question.hash.clone());
+
+            for option in &question.question_options {
+                sync_metadata
+                    .question_options_metadata
+                    .insert(option.id, option.hash.clone());
+            }
+        }
+    }
+
+    sync_metadata
+}
@@ -1,6 +1,24 @@
-use std::{ffi::OsStr, path::PathBuf};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+
+use crate::ImageData;
+
+/// Whether formatting renames image files on disk or only plans the renames.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ImageRenaming {
+    Apply,
+    Plan,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ImageRename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
 
 pub trait Hashable {
     fn hashable_data(&self) -> Vec<u8>;
@@ -16,19 +34,51 @@ pub trait WithImage: CourseAssociated + Sync {
     fn current_image_file_name(&self) -> Option<&PathBuf>;
     fn canonical_image_file_name(&self) -> String;
     fn replace_image_file_name(&mut self, new_file_name: PathBuf);
+    fn image_hash(&self) -> Option<&str>;
+    fn set_image_hash(&mut self, image_hash: String);
+
+    /// Renames the image file to its canonical name, or only plans the rename depending on
+    /// `renaming`, and hashes its content.
+    async fn format_image(
+        &mut self,
+        images_path: PathBuf,
+        renaming: ImageRenaming,
+    ) -> Result<Option<ImageRename>> {
+        let current_image_file_name = match self.current_image_file_name() {
+            Some(current_image_file_name) => current_image_file_name.clone(),
+            None => return Ok(None),
+        };
+
+        let mut image_path = images_path.join(&current_image_file_name);
+        let rename = self.image_rename(&images_path);
 
-    async fn format_image(&mut self, images_path: PathBuf) -> Result<()> {
-        if self.current_image_file_name().is_some() {
-            if let Some(new_file_name) = self.rename_image(images_path).await? {
-                self.replace_image_file_name(new_file_name);
+        if let Some(rename) = &rename {
+            if renaming == ImageRenaming::Apply {
+                tokio::fs::rename(&rename.from, &rename.to)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "renaming image {} to {}",
+                            rename.from.display(),
+                            rename.to.display()
+                        )
+                    })?;
+                image_path = rename.to.clone();
             }
+
+            self.replace_image_file_name(rename.to.file_name().unwrap().into());
         }
 
-        Ok(())
+        let image = tokio::fs::read(&image_path)
+            .await
+            .with_context(|| format!("reading image {}", image_path.display()))?;
+        self.set_image_hash(blake3::hash(&image).to_string());
+
+        Ok(rename)
     }
 
-    async fn rename_image(&self, images_path: PathBuf) -> Result<Option<PathBuf>> {
-        let current_image_file_name = self.current_image_file_name().unwrap();
+    fn image_rename(&self, images_path: &Path) -> Option<ImageRename> {
+        let current_image_file_name = self.current_image_file_name()?;
 
         let stem = current_image_file_name
             .file_stem()
@@ -44,16 +94,12 @@ pub trait WithImage: CourseAssociated + Sync {
             let mut new_file_name = PathBuf::from(self.canonical_image_file_name());
             new_file_name.set_extension(extension);
 
-            let mut old_path = images_path.clone();
-            old_path.push(current_image_file_name);
-            let mut new_path = images_path.clone();
-            new_path.push(new_file_name.clone());
-
-            tokio::fs::rename(old_path, new_path).await?;
-
-            Ok(Some(new_file_name))
+            Some(ImageRename {
+                from: images_path.join(current_image_file_name),
+                to: images_path.join(new_file_name),
+            })
         } else {
-            Ok(None)
+            None
         }
     }
 
@@ -66,6 +112,13 @@ pub trait WithImage: CourseAssociated + Sync {
                 .to_string_lossy()
         ))
     }
+
+    fn image_data(&self) -> Option<ImageData> {
+        Some(ImageData {
+            path: self.full_image_path()?,
+            hash: self.image_hash()?.to_owned(),
+        })
+    }
 }
 
 pub trait CourseAssociated {
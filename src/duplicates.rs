@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use medici_data_sync::{find_duplicates, load_courses_data, Diagnostics, QuestionData};
+
+use crate::plan::snippet;
+
+pub async fn duplicates(data_path: PathBuf, images_path: PathBuf, min_score: f64) -> Result<()> {
+    let mut diagnostics = Diagnostics::default();
+    let courses_data = load_courses_data(data_path, images_path, &mut diagnostics).await?;
+
+    eprint!("{diagnostics}");
+    diagnostics.into_result()?;
+
+    let pairs = find_duplicates(&courses_data, min_score);
+
+    for pair in &pairs {
+        println!("{:.2} {}", pair.score, describe(pair.first));
+        println!("     {}\n", describe(pair.second));
+    }
+
+    println!("{} candidate duplicate pair(s)", pairs.len());
+
+    Ok(())
+}
+
+fn describe(question: &QuestionData) -> String {
+    format!(
+        "{} {} {}",
+        question.course_key.as_deref().unwrap_or_default(),
+        question.id,
+        snippet(&question.text)
+    )
+}
@@ -0,0 +1,458 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde_json::json;
+use uuid::Uuid;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use medici_data_sync::{CourseData, QuestionData, WithImage};
+
+use super::{escape_html, explanation_html, image_file_name};
+
+/// Kept fixed so re-imported decks reuse the note type, and with it their notes.
+const MODEL_ID: i64 = 1_670_000_000_000;
+const FIELD_SEPARATOR: &str = "\x1f";
+
+const CSS: &str = ".card { font-family: arial; font-size: 20px; text-align: left; color: black; \
+                   background-color: white; }\n.card img { max-width: 100%; }";
+
+const SCHEMA: &str = "
+    CREATE TABLE col (
+        id integer primary key, crt integer not null, mod integer not null, scm integer not null,
+        ver integer not null, dty integer not null, usn integer not null, ls integer not null,
+        conf text not null, models text not null, decks text not null, dconf text not null,
+        tags text not null
+    );
+    CREATE TABLE notes (
+        id integer primary key, guid text not null, mid integer not null, mod integer not null,
+        usn integer not null, tags text not null, flds text not null, sfld integer not null,
+        csum integer not null, flags integer not null, data text not null
+    );
+    CREATE TABLE cards (
+        id integer primary key, nid integer not null, did integer not null, ord integer not null,
+        mod integer not null, usn integer not null, type integer not null, queue integer not null,
+        due integer not null, ivl integer not null, factor integer not null, reps integer not null,
+        lapses integer not null, left integer not null, odue integer not null,
+        odid integer not null, flags integer not null, data text not null
+    );
+    CREATE TABLE revlog (
+        id integer primary key, cid integer not null, usn integer not null, ivl integer not null,
+        lastIvl integer not null, factor integer not null, time integer not null,
+        type integer not null
+    );
+    CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+    CREATE INDEX ix_notes_usn on notes (usn);
+    CREATE INDEX ix_cards_usn on cards (usn);
+    CREATE INDEX ix_revlog_usn on revlog (usn);
+    CREATE INDEX ix_cards_nid on cards (nid);
+    CREATE INDEX ix_cards_sched on cards (did, queue, due);
+    CREATE INDEX ix_revlog_cid on revlog (cid);
+    CREATE INDEX ix_notes_csum on notes (csum);
+";
+
+/// Writes a `<course key>.apkg` deck per course, with a note per question.
+pub fn export(courses_data: &[CourseData], images_path: &Path, output_path: PathBuf) -> Result<()> {
+    fs::create_dir_all(&output_path)?;
+
+    for course_data in courses_data {
+        let deck_path = output_path.join(format!("{}.apkg", course_data.key));
+
+        write_deck(course_data, images_path, &deck_path)
+            .with_context(|| format!("Failed to export {}", deck_path.display()))?;
+
+        println!(
+            "Exported {} question(s) to {}",
+            course_data.questions.len(),
+            deck_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn write_deck(course_data: &CourseData, images_path: &Path, deck_path: &Path) -> Result<()> {
+    let collection_path = std::env::temp_dir().join(format!("{}.anki2", Uuid::new_v4()));
+    let result = write_collection(course_data, &collection_path)
+        .and_then(|()| write_package(course_data, images_path, &collection_path, deck_path));
+
+    fs::remove_file(&collection_path).ok();
+
+    result
+}
+
+fn write_collection(course_data: &CourseData, collection_path: &Path) -> Result<()> {
+    let connection = Connection::open(collection_path)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    let modified_at = now.as_secs() as i64;
+    let deck_id = id_from_bytes(&sha1_smol::Sha1::from(&course_data.key).digest().bytes());
+
+    connection.execute_batch(SCHEMA)?;
+    connection.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        params![
+            modified_at,
+            now.as_millis() as i64,
+            collection_config(deck_id).to_string(),
+            json!({ MODEL_ID.to_string(): model(deck_id, modified_at) }).to_string(),
+            json!({
+                "1": deck(1, "Default", modified_at),
+                deck_id.to_string(): deck(deck_id, &course_data.name, modified_at),
+            })
+            .to_string(),
+            json!({ "1": deck_config() }).to_string(),
+        ],
+    )?;
+
+    for (position, question) in course_data.questions.iter().enumerate() {
+        let note_id = id_from_bytes(question.id.as_bytes());
+        let sort_field = question.text.clone();
+        let checksum = checksum(&sort_field);
+
+        connection.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            params![
+                note_id,
+                question.id.to_string(),
+                MODEL_ID,
+                modified_at,
+                tags(question),
+                [question_field(question), answer_field(question)].join(FIELD_SEPARATOR),
+                sort_field,
+                checksum,
+            ],
+        )?;
+
+        connection.execute(
+            "INSERT INTO cards VALUES (?1, ?2, ?3, 0, ?4, -1, 0, 0, ?5, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            params![
+                id_from_bytes(&question.id.as_bytes()[8..]),
+                note_id,
+                deck_id,
+                modified_at,
+                position as i64 + 1,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Zips the collection together with the question images, which Anki expects to be named by
+/// their position in the `media` manifest.
+fn write_package(
+    course_data: &CourseData,
+    images_path: &Path,
+    collection_path: &Path,
+    deck_path: &Path,
+) -> Result<()> {
+    let mut zip = ZipWriter::new(File::create(deck_path)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("collection.anki2", options)?;
+    zip.write_all(&fs::read(collection_path)?)?;
+
+    let mut media = BTreeMap::new();
+
+    for question in &course_data.questions {
+        let (Some(image_path), Some(media_name)) =
+            (question.full_image_path(), image_file_name(question))
+        else {
+            continue;
+        };
+
+        let index = media.len().to_string();
+
+        zip.start_file(&index, options)?;
+        zip.write_all(&fs::read(images_path.join(image_path))?)?;
+        media.insert(index, media_name);
+    }
+
+    zip.start_file("media", options)?;
+    zip.write_all(serde_json::to_string(&media)?.as_bytes())?;
+    zip.finish()?;
+
+    Ok(())
+}
+
+fn question_field(question: &QuestionData) -> String {
+    let mut field = format!("<p>{}</p>", escape_html(&question.text));
+
+    if let Some(media_name) = image_file_name(question) {
+        field.push_str(&format!(
+            "<p><img src=\"{}\"></p>",
+            escape_html(&media_name)
+        ));
+    }
+
+    field.push_str("<ol type=\"A\">");
+
+    for option in &question.question_options {
+        field.push_str(&format!("<li>{}</li>", escape_html(&option.text)));
+    }
+
+    field.push_str("</ol>");
+
+    field
+}
+
+/// Marks every option as correct or not, so it works the same for every kind of question.
+fn answer_field(question: &QuestionData) -> String {
+    let mut field = String::from("<ol type=\"A\">");
+
+    for option in &question.question_options {
+        let mark = if option.correct { "✓" } else { "✗" };
+
+        field.push_str(&format!("<li>{mark} {}", escape_html(&option.text)));
+
+        if let Some(explanation) = &option.explanation {
+            field.push_str(&format!("<br><small>{}</small>", escape_html(explanation)));
+        }
+
+        field.push_str("</li>");
+    }
+
+    field.push_str("</ol>");
+
+    field.push_str(&explanation_html(question));
+
+    field
+}
+
+/// Anki tags can't contain spaces, and are wrapped in spaces themselves.
+fn tags(question: &QuestionData) -> String {
+    let tags: Vec<_> = [
+        format!("evaluation::{}", question.evaluation),
+        format!("source::{}", question.source),
+    ]
+    .into_iter()
+    .chain(
+        question
+            .topics
+            .iter()
+            .map(|topic| format!("topic::{topic}")),
+    )
+    .map(|tag| tag.replace(char::is_whitespace, "_"))
+    .collect();
+
+    format!(" {} ", tags.join(" "))
+}
+
+/// The first 32 bits of the SHA-1 of the sort field, which Anki uses to find duplicate notes.
+fn checksum(sort_field: &str) -> i64 {
+    let digest = sha1_smol::Sha1::from(sort_field).digest().bytes();
+
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]).into()
+}
+
+/// A positive ID stable across exports, as Anki IDs are 64 bits but usually millisecond timestamps.
+fn id_from_bytes(bytes: &[u8]) -> i64 {
+    let mut id_bytes = [0; 8];
+    id_bytes.copy_from_slice(&bytes[..8]);
+
+    (u64::from_be_bytes(id_bytes) >> 12) as i64
+}
+
+fn collection_config(deck_id: i64) -> serde_json::Value {
+    json!({
+        "activeDecks": [deck_id],
+        "curDeck": deck_id,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "curModel": MODEL_ID.to_string(),
+        "nextPos": 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true,
+    })
+}
+
+fn model(deck_id: i64, modified_at: i64) -> serde_json::Value {
+    let field = |name: &str, ord: i64| {
+        json!({
+            "name": name,
+            "ord": ord,
+            "sticky": false,
+            "rtl": false,
+            "font": "Arial",
+            "size": 20,
+            "media": [],
+        })
+    };
+
+    json!({
+        "id": MODEL_ID,
+        "name": "Medici question",
+        "type": 0,
+        "mod": modified_at,
+        "usn": -1,
+        "sortf": 0,
+        "did": deck_id,
+        "tmpls": [{
+            "name": "Question",
+            "ord": 0,
+            "qfmt": "{{Question}}",
+            "afmt": "{{FrontSide}}<hr id=answer>{{Answer}}",
+            "did": null,
+            "bqfmt": "",
+            "bafmt": "",
+        }],
+        "flds": [field("Question", 0), field("Answer", 1)],
+        "css": CSS,
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\
+                     \\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\
+                     \\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+        "req": [[0, "any", [0]]],
+        "tags": [],
+        "vers": [],
+    })
+}
+
+fn deck(id: i64, name: &str, modified_at: i64) -> serde_json::Value {
+    json!({
+        "id": id,
+        "name": name,
+        "desc": "",
+        "mod": modified_at,
+        "usn": -1,
+        "collapsed": false,
+        "conf": 1,
+        "dyn": 0,
+        "extendNew": 10,
+        "extendRev": 50,
+        "newToday": [0, 0],
+        "revToday": [0, 0],
+        "lrnToday": [0, 0],
+        "timeToday": [0, 0],
+    })
+}
+
+fn deck_config() -> serde_json::Value {
+    json!({
+        "id": 1,
+        "name": "Default",
+        "mod": 0,
+        "usn": 0,
+        "maxTaken": 60,
+        "autoplay": true,
+        "timer": 0,
+        "replayq": true,
+        "dyn": false,
+        "new": {
+            "delays": [1, 10],
+            "ints": [1, 4, 7],
+            "initialFactor": 2500,
+            "order": 1,
+            "perDay": 20,
+            "bury": true,
+            "separate": true,
+        },
+        "rev": {
+            "perDay": 100,
+            "ease4": 1.3,
+            "fuzz": 0.05,
+            "ivlFct": 1,
+            "maxIvl": 36500,
+            "minSpace": 1,
+            "bury": true,
+        },
+        "lapse": {
+            "delays": [10],
+            "mult": 0,
+            "minInt": 1,
+            "leechFails": 8,
+            "leechAction": 0,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use medici_data_sync::test_data::{course, question_with_kind};
+    use zip::ZipArchive;
+
+    use super::*;
+
+    #[test]
+    fn exported_decks_hold_a_note_per_question_in_the_course_deck() {
+        let course_data = course(
+            "cardio",
+            vec![question_with_kind(
+                1,
+                "multiple_choice",
+                "¿Causas de <b>shock</b>?",
+                &[("Hipovolemia", true), ("Anemia", false), ("Sepsis", true)],
+            )],
+        );
+        let output_path = std::env::temp_dir().join(format!("anki-{}", Uuid::new_v4()));
+        let collection_path = output_path.join("collection.anki2");
+
+        export(
+            std::slice::from_ref(&course_data),
+            Path::new("./images"),
+            output_path.clone(),
+        )
+        .unwrap();
+
+        let mut package =
+            ZipArchive::new(File::open(output_path.join("cardio.apkg")).unwrap()).unwrap();
+        let mut collection = vec![];
+        package
+            .by_name("collection.anki2")
+            .unwrap()
+            .read_to_end(&mut collection)
+            .unwrap();
+        let mut media = String::new();
+        package
+            .by_name("media")
+            .unwrap()
+            .read_to_string(&mut media)
+            .unwrap();
+        fs::write(&collection_path, collection).unwrap();
+
+        let connection = Connection::open(&collection_path).unwrap();
+        let decks: String = connection
+            .query_row("SELECT decks FROM col", [], |row| row.get(0))
+            .unwrap();
+        let notes: Vec<(String, String, String, i64)> = connection
+            .prepare(
+                "SELECT guid, flds, tags, cards.did FROM notes JOIN cards ON cards.nid = notes.id",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        drop(connection);
+        fs::remove_dir_all(&output_path).unwrap();
+
+        let decks: serde_json::Value = serde_json::from_str(&decks).unwrap();
+        let (guid, fields, tags, deck_id) = &notes[0];
+
+        assert_eq!(media, "{}");
+        assert_eq!(notes.len(), 1);
+        assert_eq!(decks[deck_id.to_string()]["name"], "cardio");
+        assert_eq!(guid, &course_data.questions[0].id.to_string());
+        assert_eq!(
+            fields.split(FIELD_SEPARATOR).collect::<Vec<_>>(),
+            [
+                "<p>¿Causas de &lt;b&gt;shock&lt;/b&gt;?</p><ol type=\"A\"><li>Hipovolemia</li>\
+                 <li>Sepsis</li><li>Anemia</li></ol>",
+                "<ol type=\"A\"><li>✓ Hipovolemia</li><li>✓ Sepsis</li><li>✗ Anemia</li></ol>",
+            ]
+        );
+        assert_eq!(tags, " evaluation::parcial_1 source::partial ");
+    }
+}
@@ -0,0 +1,356 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+
+use medici_data_sync::{
+    load_courses_data, CourseData, CourseEvaluationData, Diagnostics, QuestionData, QuestionSource,
+};
+
+mod anki;
+mod moodle;
+mod qti;
+mod table;
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum ExportCommand {
+    /// Export an Anki deck (.apkg) per course
+    Anki {
+        #[clap(flatten)]
+        source: ExportSource,
+
+        #[clap(
+            short,
+            long,
+            value_parser,
+            value_name = "PATH",
+            default_value = "./export"
+        )]
+        output_path: PathBuf,
+    },
+    /// Export a Moodle XML question bank per course, with images embedded
+    Moodle {
+        #[clap(flatten)]
+        source: ExportSource,
+
+        #[clap(
+            short,
+            long,
+            value_parser,
+            value_name = "PATH",
+            default_value = "./export"
+        )]
+        output_path: PathBuf,
+    },
+    /// Export a QTI 2.1 content package per course, with an assessment item per question
+    Qti {
+        #[clap(flatten)]
+        source: ExportSource,
+
+        #[clap(
+            short,
+            long,
+            value_parser,
+            value_name = "PATH",
+            default_value = "./export"
+        )]
+        output_path: PathBuf,
+    },
+    /// Export a GIFT question bank per course, for Moodle and compatible LMSs
+    Gift {
+        #[clap(flatten)]
+        source: ExportSource,
+
+        #[clap(
+            short,
+            long,
+            value_parser,
+            value_name = "PATH",
+            default_value = "./export"
+        )]
+        output_path: PathBuf,
+    },
+    /// Export a CSV table with a row per question, for spreadsheets
+    Csv {
+        #[clap(flatten)]
+        source: ExportSource,
+
+        /// Export a row per question option instead
+        #[clap(long)]
+        options: bool,
+
+        /// Write the table to the given path instead of printing it
+        #[clap(short, long, value_parser, value_name = "PATH")]
+        output_path: Option<PathBuf>,
+    },
+    /// Export a JSON object per line and question, for notebooks
+    Jsonl {
+        #[clap(flatten)]
+        source: ExportSource,
+
+        /// Export a line per question option instead
+        #[clap(long)]
+        options: bool,
+
+        /// Write the lines to the given path instead of printing them
+        #[clap(short, long, value_parser, value_name = "PATH")]
+        output_path: Option<PathBuf>,
+    },
+}
+
+/// Where to read the course data to export from.
+#[derive(Args, Clone, Debug)]
+pub struct ExportSource {
+    #[clap(
+        short,
+        long,
+        value_parser,
+        value_name = "PATH",
+        default_value = "./data"
+    )]
+    data_path: PathBuf,
+
+    #[clap(
+        short,
+        long,
+        value_parser,
+        value_name = "PATH",
+        default_value = "./images"
+    )]
+    pub images_path: PathBuf,
+
+    /// Only export the course with this key
+    #[clap(long, value_parser, value_name = "KEY")]
+    course: Option<String>,
+
+    /// Only export the questions of the evaluation with this key
+    #[clap(long, value_parser, value_name = "KEY")]
+    evaluation: Option<String>,
+
+    /// Only export the questions from this source: partial, exam, self_assessment or other
+    #[clap(long, value_parser, value_name = "SOURCE")]
+    source: Option<QuestionSource>,
+}
+
+impl ExportSource {
+    /// Loads the courses to export without writing anything back, failing on any error.
+    pub async fn load(&self) -> Result<Vec<CourseData>> {
+        let mut diagnostics = Diagnostics::default();
+        let mut courses_data = load_courses_data(
+            self.data_path.clone(),
+            self.images_path.clone(),
+            &mut diagnostics,
+        )
+        .await?;
+
+        eprint!("{diagnostics}");
+        diagnostics.into_result()?;
+
+        if let Some(course) = &self.course {
+            courses_data.retain(|course_data| &course_data.key == course);
+
+            if courses_data.is_empty() {
+                bail!("Course {course} not found");
+            }
+        }
+
+        if let Some(evaluation) = &self.evaluation {
+            for course_data in &mut courses_data {
+                course_data
+                    .questions
+                    .retain(|question| &question.evaluation == evaluation);
+                course_data
+                    .evaluations
+                    .retain(|course_evaluation| &course_evaluation.key == evaluation);
+            }
+
+            courses_data.retain(|course_data| !course_data.evaluations.is_empty());
+
+            if courses_data.is_empty() {
+                bail!("Evaluation {evaluation} not found");
+            }
+        }
+
+        if let Some(source) = self.source {
+            for course_data in &mut courses_data {
+                course_data
+                    .questions
+                    .retain(|question| question.source == source);
+            }
+
+            courses_data.retain(|course_data| !course_data.questions.is_empty());
+
+            if courses_data.is_empty() {
+                bail!("No questions from source {source} found");
+            }
+        }
+
+        courses_data.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Ok(courses_data)
+    }
+}
+
+pub async fn export(command: ExportCommand) -> Result<()> {
+    match command {
+        ExportCommand::Anki {
+            source,
+            output_path,
+        } => {
+            let courses_data = source.load().await?;
+
+            anki::export(&courses_data, &source.images_path, output_path)?;
+        }
+        ExportCommand::Moodle {
+            source,
+            output_path,
+        } => {
+            let courses_data = source.load().await?;
+
+            moodle::export_xml(&courses_data, &source.images_path, output_path)?;
+        }
+        ExportCommand::Qti {
+            source,
+            output_path,
+        } => {
+            let courses_data = source.load().await?;
+
+            qti::export(&courses_data, &source.images_path, output_path)?;
+        }
+        ExportCommand::Gift {
+            source,
+            output_path,
+        } => {
+            let courses_data = source.load().await?;
+
+            moodle::export_gift(&courses_data, output_path)?;
+        }
+        ExportCommand::Csv {
+            source,
+            options,
+            output_path,
+        } => {
+            let courses_data = source.load().await?;
+
+            table::export(&courses_data, table::TableFormat::Csv, options, output_path)?;
+        }
+        ExportCommand::Jsonl {
+            source,
+            options,
+            output_path,
+        } => {
+            let courses_data = source.load().await?;
+
+            table::export(
+                &courses_data,
+                table::TableFormat::Jsonl,
+                options,
+                output_path,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The questions of every evaluation of the course, in evaluation order.
+pub fn questions_by_evaluation(
+    course_data: &CourseData,
+) -> Vec<(&CourseEvaluationData, Vec<&QuestionData>)> {
+    let mut evaluations: Vec<_> = course_data.evaluations.iter().collect();
+    evaluations.sort_by_key(|evaluation| evaluation.order);
+
+    evaluations
+        .into_iter()
+        .map(|evaluation| {
+            let questions = course_data
+                .questions
+                .iter()
+                .filter(|question| question.evaluation == evaluation.key)
+                .collect();
+
+            (evaluation, questions)
+        })
+        .filter(|(_, questions): &(_, Vec<_>)| !questions.is_empty())
+        .collect()
+}
+
+/// Whether the question has a correct option to grade it by, warning about skipping it otherwise.
+fn is_gradable(question: &QuestionData) -> bool {
+    let is_gradable = question
+        .question_options
+        .iter()
+        .any(|option| option.correct);
+
+    if !is_gradable {
+        eprintln!(
+            "Skipping question {}, as none of its options is correct",
+            question.id
+        );
+    }
+
+    is_gradable
+}
+
+/// The file name of the question image, without the directories it may be in.
+pub fn image_file_name(question: &QuestionData) -> Option<String> {
+    Some(
+        question
+            .image_file_name
+            .as_ref()?
+            .file_name()?
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escapes text for HTML, keeping line breaks visible.
+pub fn escape_html(text: &str) -> String {
+    escape_xml(text).replace('\n', "<br>")
+}
+
+/// The question explanation followed by its references, or an empty string if it has neither.
+pub fn explanation_html(question: &QuestionData) -> String {
+    let mut html = String::new();
+
+    if let Some(explanation) = &question.explanation {
+        html.push_str(&format!("<p>{}</p>", escape_html(explanation)));
+    }
+
+    if !question.references.is_empty() {
+        html.push_str("<ul>");
+
+        for reference in &question.references {
+            let citation = [&reference.book, &reference.chapter, &reference.page]
+                .into_iter()
+                .flatten()
+                .map(|part| escape_html(part))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let link = reference.url.as_ref().map(|url| {
+                let url = escape_html(url.as_str());
+
+                format!("<a href=\"{url}\">{url}</a>")
+            });
+
+            let parts: Vec<_> = [Some(citation).filter(|citation| !citation.is_empty()), link]
+                .into_iter()
+                .flatten()
+                .collect();
+
+            html.push_str(&format!("<li>{}</li>", parts.join(" ")));
+        }
+
+        html.push_str("</ul>");
+    }
+
+    html
+}
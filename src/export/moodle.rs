@@ -0,0 +1,370 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use medici_data_sync::{CourseData, CourseEvaluationData, QuestionData, QuestionKind, WithImage};
+
+use super::{
+    escape_html, escape_xml, explanation_html, image_file_name, is_gradable,
+    questions_by_evaluation,
+};
+use crate::plan::snippet;
+
+/// Writes a `<course key>.moodle.xml` question bank per course, with a category per evaluation.
+pub fn export_xml(
+    courses_data: &[CourseData],
+    images_path: &Path,
+    output_path: PathBuf,
+) -> Result<()> {
+    fs::create_dir_all(&output_path)?;
+
+    for course_data in courses_data {
+        let path = output_path.join(format!("{}.moodle.xml", course_data.key));
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<quiz>\n");
+        let mut exported_count = 0;
+
+        for (evaluation, questions) in questions_by_evaluation(course_data) {
+            writeln!(
+                xml,
+                "  <question type=\"category\">\n    <category>\n      <text>{}</text>\n    \
+                 </category>\n  </question>",
+                escape_xml(&category(course_data, evaluation))
+            )?;
+
+            for question in questions {
+                let Some(fractions) = fractions(question) else {
+                    continue;
+                };
+
+                xml.push_str(&question_xml(question, &fractions, images_path)?);
+                exported_count += 1;
+            }
+        }
+
+        xml.push_str("</quiz>\n");
+        fs::write(&path, xml)?;
+
+        println!(
+            "Exported {exported_count} question(s) to {}",
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Writes a `<course key>.gift.txt` question bank per course, with a category per evaluation.
+/// GIFT can't embed images, so questions having one only mention its path in a comment.
+pub fn export_gift(courses_data: &[CourseData], output_path: PathBuf) -> Result<()> {
+    fs::create_dir_all(&output_path)?;
+
+    for course_data in courses_data {
+        let path = output_path.join(format!("{}.gift.txt", course_data.key));
+        let mut gift = String::new();
+        let mut exported_count = 0;
+
+        for (evaluation, questions) in questions_by_evaluation(course_data) {
+            writeln!(gift, "$CATEGORY: {}\n", category(course_data, evaluation))?;
+
+            for question in questions {
+                let Some(fractions) = fractions(question) else {
+                    continue;
+                };
+
+                gift.push_str(&question_gift(question, &fractions));
+                exported_count += 1;
+            }
+        }
+
+        fs::write(&path, gift)?;
+
+        println!(
+            "Exported {exported_count} question(s) to {}",
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// The Moodle category path of the evaluation, where a slash in a name has to be doubled.
+fn category(course_data: &CourseData, evaluation: &CourseEvaluationData) -> String {
+    format!(
+        "$course$/top/{}/{}",
+        course_data.name.replace('/', "//"),
+        evaluation.name.replace('/', "//")
+    )
+}
+
+/// The percentage of the grade each option is worth, or `None` for questions Moodle can't grade
+/// because none of their options is correct.
+fn fractions(question: &QuestionData) -> Option<Vec<String>> {
+    let correct_count = question
+        .question_options
+        .iter()
+        .filter(|option| option.correct)
+        .count();
+    let incorrect_count = question.question_options.len() - correct_count;
+
+    if !is_gradable(question) {
+        return None;
+    }
+
+    let fractions = question
+        .question_options
+        .iter()
+        .map(|option| match (question.kind, option.correct) {
+            (_, true) => format_fraction(100.0 / correct_count as f64),
+            (QuestionKind::SingleChoice, false) => "0".to_owned(),
+            (_, false) => format_fraction(-100.0 / incorrect_count as f64),
+        })
+        .collect();
+
+    Some(fractions)
+}
+
+/// Formats a fraction with the five decimals Moodle uses, such as `33.33333`.
+fn format_fraction(fraction: f64) -> String {
+    format!("{fraction:.5}")
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_owned()
+}
+
+fn question_xml(
+    question: &QuestionData,
+    fractions: &[String],
+    images_path: &Path,
+) -> Result<String> {
+    let mut text = format!("<p>{}</p>", escape_html(&question.text));
+    let mut file = String::new();
+
+    if let (Some(image_path), Some(image_file_name)) =
+        (question.full_image_path(), image_file_name(question))
+    {
+        let image = fs::read(images_path.join(image_path))?;
+
+        write!(
+            text,
+            "<p><img src=\"@@PLUGINFILE@@/{}\"></p>",
+            escape_html(&image_file_name)
+        )?;
+        write!(
+            file,
+            "\n      <file name=\"{}\" path=\"/\" encoding=\"base64\">{}</file>",
+            escape_xml(&image_file_name),
+            BASE64.encode(image)
+        )?;
+    }
+
+    let mut xml = String::new();
+
+    writeln!(xml, "  <question type=\"multichoice\">")?;
+    writeln!(
+        xml,
+        "    <name>\n      <text>{}</text>\n    </name>",
+        escape_xml(&snippet(&question.text))
+    )?;
+    writeln!(
+        xml,
+        "    <questiontext format=\"html\">\n      <text>{}</text>{file}\n    </questiontext>",
+        cdata(&text)
+    )?;
+    writeln!(
+        xml,
+        "    <generalfeedback format=\"html\">\n      <text>{}</text>\n    </generalfeedback>",
+        cdata(&explanation_html(question))
+    )?;
+    writeln!(xml, "    <idnumber>{}</idnumber>", question.id)?;
+    writeln!(xml, "    <defaultgrade>1</defaultgrade>")?;
+    writeln!(xml, "    <penalty>0</penalty>")?;
+    writeln!(
+        xml,
+        "    <single>{}</single>",
+        question.kind == QuestionKind::SingleChoice
+    )?;
+    writeln!(xml, "    <shuffleanswers>true</shuffleanswers>")?;
+    writeln!(xml, "    <answernumbering>abc</answernumbering>")?;
+
+    for (option, fraction) in question.question_options.iter().zip(fractions) {
+        let feedback = option
+            .explanation
+            .as_deref()
+            .map(escape_html)
+            .unwrap_or_default();
+
+        writeln!(
+            xml,
+            "    <answer fraction=\"{fraction}\" format=\"html\">\n      <text>{}</text>\n      \
+             <feedback format=\"html\">\n        <text>{}</text>\n      </feedback>\n    </answer>",
+            cdata(&escape_html(&option.text)),
+            cdata(&feedback)
+        )?;
+    }
+
+    writeln!(xml, "  </question>")?;
+
+    Ok(xml)
+}
+
+fn question_gift(question: &QuestionData, fractions: &[String]) -> String {
+    let mut gift = format!("// {}\n", question.id);
+
+    if let Some(image_path) = question.full_image_path() {
+        gift.push_str(&format!("// Image: {image_path}\n"));
+    }
+
+    gift.push_str(&format!(
+        "::{}::[html]{}{{\n",
+        escape_gift(&snippet(&question.text)),
+        escape_gift(&escape_html(&question.text))
+    ));
+
+    for (option, fraction) in question.question_options.iter().zip(fractions) {
+        let marker = match (question.kind, option.correct) {
+            (QuestionKind::SingleChoice, true) => "=".to_owned(),
+            (QuestionKind::SingleChoice, false) => "~".to_owned(),
+            _ => format!("~%{fraction}%"),
+        };
+
+        gift.push_str(&format!(
+            "\t{marker}{}",
+            escape_gift(&escape_html(&option.text))
+        ));
+
+        if let Some(explanation) = &option.explanation {
+            gift.push_str(&format!("#{}", escape_gift(&escape_html(explanation))));
+        }
+
+        gift.push('\n');
+    }
+
+    let general_feedback = explanation_html(question);
+
+    if !general_feedback.is_empty() {
+        gift.push_str(&format!("\t####{}\n", escape_gift(&general_feedback)));
+    }
+
+    gift.push_str("}\n\n");
+
+    gift
+}
+
+fn escape_gift(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        if matches!(character, '~' | '=' | '#' | '{' | '}' | ':' | '\\') {
+            escaped.push('\\');
+        }
+
+        escaped.push(character);
+    }
+
+    escaped
+}
+
+/// Wraps text in a CDATA section, splitting it wherever the text would end the section early.
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+#[cfg(test)]
+mod tests {
+    use medici_data_sync::test_data::{course, question_with_kind};
+
+    use super::*;
+
+    fn question(kind: &str, options: &[(&str, bool)]) -> QuestionData {
+        course(
+            "cardio",
+            vec![question_with_kind(1, kind, "Question", options)],
+        )
+        .questions
+        .remove(0)
+    }
+
+    /// The fraction of each option by its text.
+    fn option_fractions(kind: &str, options: &[(&str, bool)]) -> Option<Vec<(String, String)>> {
+        let question = question(kind, options);
+        let fractions = fractions(&question)?;
+
+        Some(
+            question
+                .question_options
+                .iter()
+                .map(|option| option.text.clone())
+                .zip(fractions)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn fractions_split_the_grade_among_the_correct_options() {
+        let cases = [
+            (
+                "single_choice",
+                vec![("A", true), ("B", false), ("C", false)],
+                vec![("A", "100"), ("B", "0"), ("C", "0")],
+            ),
+            (
+                "multiple_choice",
+                vec![("A", true), ("B", false), ("C", true), ("D", false)],
+                vec![("A", "50"), ("C", "50"), ("B", "-50"), ("D", "-50")],
+            ),
+            (
+                "multiple_choice",
+                vec![("A", true), ("B", true), ("C", true), ("D", false)],
+                vec![
+                    ("A", "33.33333"),
+                    ("B", "33.33333"),
+                    ("C", "33.33333"),
+                    ("D", "-100"),
+                ],
+            ),
+            (
+                "true_false",
+                vec![("A", false), ("B", true), ("C", false)],
+                vec![("A", "-50"), ("B", "100"), ("C", "-50")],
+            ),
+        ];
+
+        for (kind, options, expected) in cases {
+            let expected: Vec<_> = expected
+                .into_iter()
+                .map(|(text, fraction)| (text.to_owned(), fraction.to_owned()))
+                .collect();
+
+            assert_eq!(option_fractions(kind, &options), Some(expected), "{kind}");
+        }
+    }
+
+    #[test]
+    fn fractions_leave_out_questions_without_correct_options() {
+        assert_eq!(
+            option_fractions("true_false", &[("A", false), ("B", false)]),
+            None
+        );
+    }
+
+    #[test]
+    fn escape_gift_escapes_the_special_characters() {
+        assert_eq!(escape_gift(r"a~b=c#d{e}f:g\h"), r"a\~b\=c\#d\{e\}f\:g\\h");
+    }
+
+    #[test]
+    fn question_gift_escapes_the_question_and_weights_multiple_answers() {
+        let question = question(
+            "multiple_choice",
+            &[("x = 1", true), ("{y}: 2", true), ("~z #3", false)],
+        );
+        let gift = question_gift(&question, &fractions(&question).unwrap());
+
+        assert!(gift.contains("\t~%50%x \\= 1\n"), "{gift}");
+        assert!(gift.contains("\t~%50%\\{y\\}\\: 2\n"), "{gift}");
+        assert!(gift.contains("\t~%-100%\\~z \\#3\n"), "{gift}");
+    }
+}
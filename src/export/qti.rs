@@ -0,0 +1,285 @@
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use medici_data_sync::{CourseData, QuestionData, QuestionKind, WithImage};
+
+use super::{escape_xml, image_file_name, is_gradable};
+use crate::plan::snippet;
+
+const ITEMS_PATH: &str = "items";
+const IMAGES_PATH: &str = "images";
+
+/// Writes a `<course key>.qti.zip` IMS content package per course, with a QTI 2.1 assessment
+/// item per question.
+pub fn export(courses_data: &[CourseData], images_path: &Path, output_path: PathBuf) -> Result<()> {
+    fs::create_dir_all(&output_path)?;
+
+    for course_data in courses_data {
+        let package_path = output_path.join(format!("{}.qti.zip", course_data.key));
+
+        let exported_count = write_package(course_data, images_path, &package_path)
+            .with_context(|| format!("Failed to export {}", package_path.display()))?;
+
+        println!(
+            "Exported {exported_count} question(s) to {}",
+            package_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn write_package(
+    course_data: &CourseData,
+    images_path: &Path,
+    package_path: &Path,
+) -> Result<usize> {
+    let mut zip = ZipWriter::new(File::create(package_path)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut resources = String::new();
+    let mut exported_count = 0;
+
+    for question in course_data
+        .questions
+        .iter()
+        .filter(|question| is_gradable(question))
+    {
+        let identifier = item_identifier(question);
+        let item_path = format!("{ITEMS_PATH}/{identifier}.xml");
+        let mut files = vec![item_path.clone()];
+
+        zip.start_file(&item_path, options)?;
+        zip.write_all(assessment_item(question).as_bytes())?;
+
+        if let (Some(image_path), Some(image_file_name)) =
+            (question.full_image_path(), image_file_name(question))
+        {
+            let package_image_path = format!("{ITEMS_PATH}/{IMAGES_PATH}/{image_file_name}");
+
+            zip.start_file(&package_image_path, options)?;
+            zip.write_all(&fs::read(images_path.join(image_path))?)?;
+            files.push(package_image_path);
+        }
+
+        writeln!(
+            resources,
+            "    <resource identifier=\"{identifier}\" type=\"imsqti_item_xmlv2p1\" href=\"{}\">",
+            escape_xml(&item_path)
+        )?;
+
+        for file in files {
+            writeln!(resources, "      <file href=\"{}\"/>", escape_xml(&file))?;
+        }
+
+        writeln!(resources, "    </resource>")?;
+        exported_count += 1;
+    }
+
+    zip.start_file("imsmanifest.xml", options)?;
+    zip.write_all(manifest(course_data, &resources).as_bytes())?;
+    zip.finish()?;
+
+    Ok(exported_count)
+}
+
+fn manifest(course_data: &CourseData, resources: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest xmlns="http://www.imsglobal.org/xsd/imscp_v1p1"
+  xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+  xsi:schemaLocation="http://www.imsglobal.org/xsd/imscp_v1p1 http://www.imsglobal.org/xsd/qti/qtiv2p1/qtiv2p1_imscpv1p2_v1p0.xsd"
+  identifier="manifest-{}">
+  <metadata>
+    <schema>QTIv2.1 Package</schema>
+    <schemaversion>1.0.0</schemaversion>
+  </metadata>
+  <organizations/>
+  <resources>
+{resources}  </resources>
+</manifest>
+"#,
+        escape_xml(&course_data.key)
+    )
+}
+
+/// A choice interaction scored with the standard `match_correct` template, so a response is
+/// correct only if it selects exactly the correct options.
+fn assessment_item(question: &QuestionData) -> String {
+    let (cardinality, max_choices) = match question.kind {
+        QuestionKind::SingleChoice => ("single", 1),
+        QuestionKind::MultipleChoice | QuestionKind::TrueFalse => ("multiple", 0),
+    };
+
+    let correct_values: String = question
+        .question_options
+        .iter()
+        .filter(|option| option.correct)
+        .map(|option| format!("      <value>o-{}</value>\n", option.id))
+        .collect();
+
+    let image = image_file_name(question)
+        .map(|image_file_name| {
+            format!(
+                "    <p><img src=\"{IMAGES_PATH}/{}\" alt=\"\"/></p>\n",
+                escape_xml(&image_file_name)
+            )
+        })
+        .unwrap_or_default();
+
+    let choices: String = question
+        .question_options
+        .iter()
+        .map(|option| {
+            format!(
+                "      <simpleChoice identifier=\"o-{}\">{}</simpleChoice>\n",
+                option.id,
+                escape_xhtml(&option.text)
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<assessmentItem xmlns="http://www.imsglobal.org/xsd/imsqti_v2p1"
+  xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+  xsi:schemaLocation="http://www.imsglobal.org/xsd/imsqti_v2p1 http://www.imsglobal.org/xsd/qti/qtiv2p1/imsqti_v2p1.xsd"
+  identifier="{identifier}" title="{title}" adaptive="false" timeDependent="false">
+  <responseDeclaration identifier="RESPONSE" cardinality="{cardinality}" baseType="identifier">
+    <correctResponse>
+{correct_values}    </correctResponse>
+  </responseDeclaration>
+  <outcomeDeclaration identifier="SCORE" cardinality="single" baseType="float">
+    <defaultValue>
+      <value>0</value>
+    </defaultValue>
+  </outcomeDeclaration>
+  <itemBody>
+    <p>{text}</p>
+{image}    <choiceInteraction responseIdentifier="RESPONSE" shuffle="true" maxChoices="{max_choices}">
+{choices}    </choiceInteraction>
+  </itemBody>
+  <responseProcessing template="http://www.imsglobal.org/question/qti_v2p1/rptemplates/match_correct"/>
+</assessmentItem>
+"#,
+        identifier = item_identifier(question),
+        title = escape_xml(&snippet(&question.text)),
+        text = escape_xhtml(&question.text),
+    )
+}
+
+/// QTI identifiers can't start with a digit, unlike UUIDs.
+fn item_identifier(question: &QuestionData) -> String {
+    format!("q-{}", question.id)
+}
+
+fn escape_xhtml(text: &str) -> String {
+    escape_xml(text).replace('\n', "<br/>")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use medici_data_sync::test_data::{course, question_with_kind};
+    use uuid::Uuid;
+    use zip::ZipArchive;
+
+    use super::*;
+
+    /// The identifiers in the item's correct response, and its cardinality and maximum choices.
+    fn response(kind: &str, options: &[(&str, bool)]) -> (Vec<String>, String, String) {
+        let question = course(
+            "cardio",
+            vec![question_with_kind(1, kind, "Question", options)],
+        )
+        .questions
+        .remove(0);
+        let item = assessment_item(&question);
+        let attribute = |name: &str| {
+            let start = item.find(&format!("{name}=\"")).unwrap() + name.len() + 2;
+
+            item[start..start + item[start..].find('"').unwrap()].to_owned()
+        };
+        let values = item
+            .split("<value>")
+            .skip(1)
+            .map(|value| value[..value.find("</value>").unwrap()].to_owned())
+            .filter(|value| value.starts_with("o-"))
+            .collect();
+
+        (values, attribute("cardinality"), attribute("maxChoices"))
+    }
+
+    fn option_identifier(id: u128) -> String {
+        format!("o-{}", Uuid::from_u128(id))
+    }
+
+    #[test]
+    fn assessment_items_declare_the_correct_response_by_kind() {
+        assert_eq!(
+            response("single_choice", &[("A", false), ("B", true), ("C", false)]),
+            (
+                vec![option_identifier(12)],
+                "single".to_owned(),
+                "1".to_owned()
+            )
+        );
+        assert_eq!(
+            response("multiple_choice", &[("A", true), ("B", false), ("C", true)]),
+            (
+                vec![option_identifier(11), option_identifier(13)],
+                "multiple".to_owned(),
+                "0".to_owned()
+            )
+        );
+        assert_eq!(
+            response("true_false", &[("A", false), ("B", true)]),
+            (
+                vec![option_identifier(12)],
+                "multiple".to_owned(),
+                "0".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn packages_list_every_gradable_item_in_the_manifest() {
+        let course_data = course(
+            "cardio",
+            vec![
+                question_with_kind(1, "single_choice", "One", &[("A", true), ("B", false)]),
+                question_with_kind(2, "true_false", "Two", &[("A", false), ("B", false)]),
+            ],
+        );
+        let output_path = std::env::temp_dir().join(format!("qti-{}", Uuid::new_v4()));
+
+        export(&[course_data], Path::new("./images"), output_path.clone()).unwrap();
+
+        let mut package =
+            ZipArchive::new(File::open(output_path.join("cardio.qti.zip")).unwrap()).unwrap();
+        let mut manifest = String::new();
+        package
+            .by_name("imsmanifest.xml")
+            .unwrap()
+            .read_to_string(&mut manifest)
+            .unwrap();
+        let file_names: Vec<_> = package.file_names().map(str::to_owned).collect();
+        fs::remove_dir_all(&output_path).unwrap();
+
+        let identifier = format!("q-{}", Uuid::from_u128(1));
+
+        assert!(manifest.contains(&format!(
+            "    <resource identifier=\"{identifier}\" type=\"imsqti_item_xmlv2p1\" \
+             href=\"items/{identifier}.xml\">\n      <file href=\"items/{identifier}.xml\"/>\n    \
+             </resource>\n"
+        )));
+        assert_eq!(manifest.matches("<resource ").count(), 1);
+        assert!(file_names.contains(&format!("items/{identifier}.xml")));
+        assert_eq!(file_names.len(), 2);
+    }
+}
@@ -0,0 +1,147 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+use uuid::Uuid;
+
+use medici_data_sync::{CourseAssociated, CourseData, QuestionData, QuestionSource};
+
+#[derive(Clone, Copy, Debug)]
+pub enum TableFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Serialize, Debug)]
+struct QuestionRow<'a> {
+    course_key: &'a str,
+    question_id: Uuid,
+    evaluation: &'a str,
+    source: QuestionSource,
+    asked_at: Option<NaiveDate>,
+    text: &'a str,
+    correct_options: String,
+    option_count: usize,
+    has_image: bool,
+    hash: &'a str,
+}
+
+#[derive(Serialize, Debug)]
+struct OptionRow<'a> {
+    course_key: &'a str,
+    question_id: Uuid,
+    option_id: Uuid,
+    position: usize,
+    text: &'a str,
+    correct: bool,
+    hash: &'a str,
+}
+
+/// Writes a row per question, or per question option, of every course to the given path or
+/// standard output.
+pub fn export(
+    courses_data: &[CourseData],
+    format: TableFormat,
+    options: bool,
+    output_path: Option<PathBuf>,
+) -> Result<()> {
+    let writer: Box<dyn Write> = match &output_path {
+        Some(output_path) => Box::new(File::create(output_path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut writer = RowWriter::new(writer, format);
+    let questions = courses_data
+        .iter()
+        .flat_map(|course_data| &course_data.questions);
+    let mut row_count = 0;
+
+    for question in questions {
+        if options {
+            for (position, option) in question.question_options.iter().enumerate() {
+                writer.write(&OptionRow {
+                    course_key: question.course_key(),
+                    question_id: question.id,
+                    option_id: option.id,
+                    position: position + 1,
+                    text: &option.text,
+                    correct: option.correct,
+                    hash: &option.hash,
+                })?;
+                row_count += 1;
+            }
+        } else {
+            writer.write(&question_row(question))?;
+            row_count += 1;
+        }
+    }
+
+    writer.flush()?;
+
+    if let Some(output_path) = output_path {
+        eprintln!("Exported {row_count} row(s) to {}", output_path.display());
+    }
+
+    Ok(())
+}
+
+fn question_row(question: &QuestionData) -> QuestionRow<'_> {
+    // Multiple choice questions have several correct options, which have to fit in a cell.
+    let correct_options = question
+        .question_options
+        .iter()
+        .filter(|option| option.correct)
+        .map(|option| option.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    QuestionRow {
+        course_key: question.course_key(),
+        question_id: question.id,
+        evaluation: &question.evaluation,
+        source: question.source,
+        asked_at: question.asked_at,
+        text: &question.text,
+        correct_options,
+        option_count: question.question_options.len(),
+        has_image: question.image_file_name.is_some(),
+        hash: &question.hash,
+    }
+}
+
+enum RowWriter {
+    Csv(Box<csv::Writer<Box<dyn Write>>>),
+    Jsonl(Box<dyn Write>),
+}
+
+impl RowWriter {
+    fn new(writer: Box<dyn Write>, format: TableFormat) -> Self {
+        match format {
+            TableFormat::Csv => Self::Csv(Box::new(csv::Writer::from_writer(writer))),
+            TableFormat::Jsonl => Self::Jsonl(writer),
+        }
+    }
+
+    fn write(&mut self, row: &impl Serialize) -> Result<()> {
+        match self {
+            Self::Csv(writer) => writer.serialize(row)?,
+            Self::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, row)?;
+                writeln!(writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            Self::Csv(writer) => writer.flush()?,
+            Self::Jsonl(writer) => writer.flush()?,
+        }
+
+        Ok(())
+    }
+}
@@ -1,15 +1,66 @@
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use similar::TextDiff;
 
-use medici_data_sync::{read_data_dir, CourseData};
+use medici_data_sync::{
+    display_path, read_data_dir, CourseData, Diagnostics, FormattedCourseData, ImageRenaming,
+};
 
-pub async fn format(data_path: PathBuf, images_path: PathBuf) -> Result<()> {
+pub async fn format(data_path: PathBuf, images_path: PathBuf, check: bool) -> Result<()> {
     let entries = read_data_dir(data_path)?;
+    let mut diagnostics = Diagnostics::default();
+    let mut unformatted_count = 0;
 
     for dir_entry in entries {
-        CourseData::load_and_write_formatted(dir_entry?, images_path.clone()).await?;
+        if !check {
+            CourseData::load_and_write_formatted(dir_entry?, images_path.clone(), &mut diagnostics)
+                .await?;
+
+            continue;
+        }
+
+        let formatted = CourseData::load_formatted(
+            dir_entry?,
+            images_path.clone(),
+            ImageRenaming::Plan,
+            &mut diagnostics,
+        )
+        .await?;
+
+        if let Some(formatted) = formatted.filter(|formatted| !formatted.is_formatted()) {
+            print_changes(&formatted);
+
+            unformatted_count += 1;
+        }
+    }
+
+    eprint!("{diagnostics}");
+    diagnostics.into_result()?;
+
+    if unformatted_count > 0 {
+        bail!("{unformatted_count} file(s) would be reformatted, run `data-sync format`");
     }
 
     Ok(())
 }
+
+pub fn print_changes(formatted: &FormattedCourseData) {
+    let path = display_path(&formatted.path).display().to_string();
+    let source = String::from_utf8_lossy(&formatted.source);
+
+    print!(
+        "{}",
+        TextDiff::from_lines(source.as_ref(), formatted.formatted.as_str())
+            .unified_diff()
+            .header(&path, &path)
+    );
+
+    for image_rename in &formatted.image_renames {
+        println!(
+            "rename {} -> {}",
+            display_path(&image_rename.from).display(),
+            display_path(&image_rename.to).display()
+        );
+    }
+}
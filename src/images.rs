@@ -0,0 +1,163 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use reqwest::header::{HeaderMap, HeaderValue, CACHE_CONTROL};
+use s3::{creds::Credentials, Bucket, Region};
+use url::Url;
+
+use medici_data_sync::{EntityPlan, ImageData};
+
+const CACHE_CONTROL_VALUE: &str = "public, max-age=86400";
+
+pub struct ImagesBucket {
+    bucket: Bucket,
+}
+
+impl ImagesBucket {
+    pub fn new(name: &str, region: String, endpoint: Option<Url>) -> Result<Self> {
+        let credentials = Credentials::default()?;
+
+        let bucket = match endpoint {
+            Some(endpoint) => Bucket::new(
+                name,
+                Region::Custom {
+                    region,
+                    endpoint: endpoint.as_str().trim_end_matches('/').to_owned(),
+                },
+                credentials,
+            )?
+            .with_path_style(),
+            None => Bucket::new(name, region.parse()?, credentials)?,
+        };
+
+        Ok(Self { bucket })
+    }
+
+    /// Uploads the images whose content differs from the bucket's copy or changed since the last
+    /// sync.
+    pub async fn upload(
+        &self,
+        images_path: PathBuf,
+        images: &[ImageData],
+        images_plan: Option<&EntityPlan<String, ImageData>>,
+    ) -> Result<()> {
+        let remote_images = self.list().await?;
+        let changed_images: HashSet<_> = images_plan
+            .into_iter()
+            .flat_map(|images_plan| &images_plan.changed)
+            .map(|image| &image.path)
+            .collect();
+        let upload_bucket = self.bucket.with_extra_headers(HeaderMap::from_iter([(
+            CACHE_CONTROL,
+            HeaderValue::from_static(CACHE_CONTROL_VALUE),
+        )]));
+
+        // Fail before uploading anything rather than halfway through.
+        for image in images {
+            content_type(Path::new(&image.path))?;
+        }
+
+        let mut uploaded_count = 0;
+
+        for image in images {
+            let mut path = images_path.clone();
+            path.push(&image.path);
+
+            let content = tokio::fs::read(&path).await?;
+            let content_hash = format!("{:x}", md5::compute(&content));
+
+            // The ETag of objects uploaded in multiple parts isn't their MD5 hash, so a changed
+            // content hash in the engine is worth an upload too.
+            if remote_images.get(&image.path) == Some(&content_hash)
+                && !changed_images.contains(&image.path)
+            {
+                continue;
+            }
+
+            upload_bucket
+                .put_object_with_content_type(&image.path, &content, content_type(&path)?)
+                .await?;
+
+            uploaded_count += 1;
+        }
+
+        println!("Uploaded {uploaded_count} image(s)");
+
+        Ok(())
+    }
+
+    /// Deletes the objects that don't belong to any image. Meant to run once the engine no longer
+    /// points to them.
+    pub async fn prune(&self, images: &[ImageData]) -> Result<()> {
+        let image_paths: HashSet<_> = images.iter().map(|image| image.path.as_str()).collect();
+        let mut deleted_count = 0;
+
+        for key in self.list().await?.keys() {
+            if !image_paths.contains(key.as_str()) {
+                self.bucket.delete_object(key).await?;
+                deleted_count += 1;
+            }
+        }
+
+        println!("Deleted {deleted_count} image(s)");
+
+        Ok(())
+    }
+
+    /// Maps each object key in the bucket to its ETag, which is the MD5 hash of its content.
+    async fn list(&self) -> Result<HashMap<String, String>> {
+        let mut images = HashMap::new();
+
+        for page in self.bucket.list(String::new(), None).await? {
+            for object in page.contents {
+                let e_tag = object.e_tag.unwrap_or_default();
+
+                images.insert(object.key, e_tag.trim_matches('"').to_owned());
+            }
+        }
+
+        Ok(images)
+    }
+}
+
+/// The content type of an image by its extension, failing on anything browsers may not display.
+pub fn content_type(path: &Path) -> Result<&'static str> {
+    match path.extension().and_then(OsStr::to_str) {
+        Some("avif") => Ok("image/avif"),
+        Some("webp") => Ok("image/webp"),
+        Some("png") => Ok("image/png"),
+        Some("jpg" | "jpeg") => Ok("image/jpeg"),
+        Some("gif") => Ok("image/gif"),
+        Some("svg") => Ok("image/svg+xml"),
+        _ => bail!("Unknown image type of {}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_type_follows_the_extension() {
+        for (file_name, expected) in [
+            ("spyh/5f0c.avif", "image/avif"),
+            ("cardio/cardio.webp", "image/webp"),
+            ("a.png", "image/png"),
+            ("a.jpg", "image/jpeg"),
+            ("a.jpeg", "image/jpeg"),
+            ("a.gif", "image/gif"),
+            ("a.svg", "image/svg+xml"),
+        ] {
+            assert_eq!(content_type(Path::new(file_name)).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn content_type_fails_on_unknown_extensions() {
+        for file_name in ["a.bmp", "a.AVIF", "a"] {
+            assert!(content_type(Path::new(file_name)).is_err(), "{file_name}");
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use csv::{ErrorKind, ReaderBuilder, StringRecord};
+use serde::Deserialize;
+
+use medici_data_sync::{
+    Diagnostic, Diagnostics, QuestionKind, QuestionSource, RawQuestionData, RawQuestionOptionData,
+    SourceMap,
+};
+
+use super::ImportedQuestion;
+
+const OPTION_LETTERS: [char; 5] = ['a', 'b', 'c', 'd', 'e'];
+
+#[derive(Deserialize, Debug)]
+struct Row {
+    text: String,
+    a: Option<String>,
+    b: Option<String>,
+    c: Option<String>,
+    d: Option<String>,
+    e: Option<String>,
+    correct: Option<String>,
+    evaluation: String,
+    source: QuestionSource,
+    asked_at: Option<NaiveDate>,
+    image: Option<PathBuf>,
+    kind: Option<QuestionKind>,
+    explanation: Option<String>,
+}
+
+/// Reads a question per row, failing with every row that can't be turned into a question.
+pub fn parse(input_path: &Path) -> Result<Vec<ImportedQuestion>> {
+    let input = fs::read(input_path)?;
+    let mut diagnostics = Diagnostics::default();
+    let questions = parse_source(input_path, &input, &mut diagnostics)?;
+
+    eprint!("{diagnostics}");
+    diagnostics.into_result()?;
+
+    Ok(questions)
+}
+
+fn parse_source(
+    input_path: &Path,
+    input: &[u8],
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<ImportedQuestion>> {
+    let source_map = SourceMap::lines(input);
+    let mut reader = ReaderBuilder::new().from_reader(input);
+
+    // Spreadsheets tend to capitalize headers, like `Text` or `A`.
+    let headers: StringRecord = reader
+        .headers()?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    reader.set_headers(headers.clone());
+
+    let mut questions = vec![];
+
+    for record in reader.records() {
+        let (position, question) = match record {
+            Ok(record) => (
+                record.position().cloned(),
+                record
+                    .deserialize::<Row>(Some(&headers))
+                    .map_err(|error| error_message(&error))
+                    .and_then(raw_question),
+            ),
+            Err(error) => (error.position().cloned(), Err(error_message(&error))),
+        };
+        let line = position.map_or(0, |position| position.line() as usize);
+        let location = source_map.locate_line_byte_column(line, 1);
+
+        match question {
+            Ok(raw) => questions.push(ImportedQuestion {
+                location: location.clone(),
+                raw,
+            }),
+            Err(message) => {
+                let mut diagnostic = Diagnostic::error("", format!("Row {line}: {message}"))
+                    .with_file(input_path.to_owned());
+
+                if let Some(location) = location {
+                    diagnostic = diagnostic.with_location(location);
+                }
+
+                diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    Ok(questions)
+}
+
+/// The message of a CSV error, leaving out the position that diagnostics already show.
+fn error_message(error: &csv::Error) -> String {
+    match error.kind() {
+        ErrorKind::Deserialize { err, .. } => err.to_string(),
+        ErrorKind::UnequalLengths {
+            expected_len, len, ..
+        } => format!("Found {len} field(s), expected {expected_len}"),
+        ErrorKind::Utf8 { err, .. } => format!("Invalid UTF-8: {err}"),
+        _ => error.to_string(),
+    }
+}
+
+fn raw_question(row: Row) -> Result<RawQuestionData, String> {
+    let correct_letters: Vec<char> = row
+        .correct
+        .unwrap_or_default()
+        .split(|character: char| character == ',' || character.is_whitespace())
+        .filter(|letter| !letter.is_empty())
+        .map(|letter| match letter.to_lowercase().as_str() {
+            letter @ ("a" | "b" | "c" | "d" | "e") => Ok(letter.chars().next().unwrap()),
+            _ => Err(format!(
+                "Correct option {} is not one of A to E",
+                letter.to_uppercase()
+            )),
+        })
+        .collect::<Result<_, _>>()?;
+
+    let kind = row.kind.unwrap_or(if correct_letters.len() > 1 {
+        QuestionKind::MultipleChoice
+    } else {
+        QuestionKind::SingleChoice
+    });
+
+    if correct_letters.is_empty() && kind != QuestionKind::TrueFalse {
+        return Err("No correct option".to_owned());
+    }
+
+    let option_texts = [row.a, row.b, row.c, row.d, row.e];
+
+    for letter in &correct_letters {
+        let index = OPTION_LETTERS
+            .iter()
+            .position(|other| other == letter)
+            .unwrap();
+
+        if option_texts[index].is_none() {
+            return Err(format!(
+                "Correct option {} is empty",
+                letter.to_ascii_uppercase()
+            ));
+        }
+    }
+
+    let options = OPTION_LETTERS
+        .iter()
+        .zip(option_texts)
+        .filter_map(|(letter, text)| {
+            Some(RawQuestionOptionData {
+                id: None,
+                text: text?,
+                correct: correct_letters.contains(letter).then_some(true),
+                explanation: None,
+            })
+        })
+        .collect();
+
+    Ok(RawQuestionData {
+        id: None,
+        kind,
+        evaluation: row.evaluation,
+        source: row.source,
+        asked_at: row.asked_at,
+        text: row.text,
+        image: row.image,
+        options,
+        explanation: row.explanation,
+        references: vec![],
+        topics: vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(correct: &str, options: [Option<&str>; 5], kind: Option<QuestionKind>) -> Row {
+        let [a, b, c, d, e] = options.map(|option| option.map(ToOwned::to_owned));
+
+        Row {
+            text: "Question".to_owned(),
+            a,
+            b,
+            c,
+            d,
+            e,
+            correct: Some(correct.to_owned()).filter(|correct| !correct.is_empty()),
+            evaluation: "parcial_1".to_owned(),
+            source: QuestionSource::Partial,
+            asked_at: None,
+            image: None,
+            kind,
+            explanation: None,
+        }
+    }
+
+    fn correct_texts(question: &RawQuestionData) -> Vec<&str> {
+        question
+            .options
+            .iter()
+            .filter(|option| option.correct == Some(true))
+            .map(|option| option.text.as_str())
+            .collect()
+    }
+
+    const OPTIONS: [Option<&str>; 5] = [Some("A"), Some("B"), Some("C"), Some("D"), None];
+
+    #[test]
+    fn raw_question_parses_correct_letters() {
+        for (correct, expected) in [
+            ("a", vec!["A"]),
+            ("C", vec!["C"]),
+            ("a,c", vec!["A", "C"]),
+            (" B, d ", vec!["B", "D"]),
+            ("a c", vec!["A", "C"]),
+        ] {
+            let question = raw_question(row(correct, OPTIONS, None)).unwrap();
+
+            assert_eq!(correct_texts(&question), expected, "{correct}");
+            assert_eq!(question.options.len(), 4);
+        }
+    }
+
+    #[test]
+    fn raw_question_rejects_unknown_and_missing_letters() {
+        for (correct, expected) in [
+            ("f", "Correct option F is not one of A to E"),
+            ("a;b", "Correct option A;B is not one of A to E"),
+            ("", "No correct option"),
+        ] {
+            assert_eq!(
+                raw_question(row(correct, OPTIONS, None)).unwrap_err(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn raw_question_rejects_correct_letters_of_empty_options() {
+        assert_eq!(
+            raw_question(row("a,e", OPTIONS, None)).unwrap_err(),
+            "Correct option E is empty"
+        );
+    }
+
+    #[test]
+    fn raw_question_infers_the_kind_from_the_correct_letters() {
+        for (correct, kind, expected) in [
+            ("a", None, QuestionKind::SingleChoice),
+            ("a,b", None, QuestionKind::MultipleChoice),
+            (
+                "a",
+                Some(QuestionKind::MultipleChoice),
+                QuestionKind::MultipleChoice,
+            ),
+            ("", Some(QuestionKind::TrueFalse), QuestionKind::TrueFalse),
+        ] {
+            let question = raw_question(row(correct, OPTIONS, kind)).unwrap();
+
+            assert_eq!(question.kind, expected, "{correct} {kind:?}");
+        }
+    }
+
+    #[test]
+    fn parse_source_reports_malformed_records_and_keeps_going() {
+        let input = "Text,A,B,Correct,Evaluation,Source\n\
+                     One,A,B,a,parcial_1,partial\n\
+                     Two,A,B\n\
+                     Three,A,B,x,parcial_1,partial\n\
+                     Four,A,B,b,parcial_1,partial\n";
+        let mut diagnostics = Diagnostics::default();
+
+        let questions = parse_source(
+            Path::new("questions.csv"),
+            input.as_bytes(),
+            &mut diagnostics,
+        )
+        .unwrap();
+        let errors: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.message.as_str(),
+                    diagnostic.location.as_ref().map(|location| location.line),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            questions
+                .iter()
+                .map(|question| question.raw.text.as_str())
+                .collect::<Vec<_>>(),
+            ["One", "Four"]
+        );
+        assert_eq!(
+            errors,
+            [
+                ("Row 3: Found 3 field(s), expected 6", Some(3)),
+                ("Row 4: Correct option X is not one of A to E", Some(4)),
+            ]
+        );
+    }
+}
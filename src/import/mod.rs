@@ -0,0 +1,225 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+
+use medici_data_sync::{
+    display_path, CourseData, Diagnostic, Diagnostics, FormattedCourseData, ImageRenaming,
+    RawCourseData, RawQuestionData, SourceLocation,
+};
+
+use crate::format::print_changes;
+
+mod csv;
+mod text;
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum ImportCommand {
+    /// Import questions from a CSV file with a header row and a row per question
+    ///
+    /// The columns are `text`, the options `a` to `e`, the `correct` option letters, `evaluation`,
+    /// `source`, and optionally `asked_at`, `image`, `kind` and `explanation`. Images are file names
+    /// in the course images directory.
+    Csv {
+        #[clap(flatten)]
+        target: ImportTarget,
+
+        #[clap(value_parser, value_name = "PATH")]
+        input_path: PathBuf,
+    },
+    /// Import questions transcribed from an exam as numbered questions with lettered options
+    ///
+    /// Correct options are either marked, like `b) Option *`, or listed in an answer key at the
+    /// end, like `Respuestas: 1-B, 2-A`. The changes to the course are only previewed unless
+    /// `--write` is passed.
+    Text {
+        #[clap(flatten)]
+        target: ImportTarget,
+
+        #[clap(flatten)]
+        options: text::TextImportOptions,
+
+        #[clap(value_parser, value_name = "PATH")]
+        input_path: PathBuf,
+    },
+}
+
+/// The course the questions are imported into.
+#[derive(Args, Clone, Debug)]
+pub struct ImportTarget {
+    #[clap(
+        short,
+        long,
+        value_parser,
+        value_name = "PATH",
+        default_value = "./data"
+    )]
+    data_path: PathBuf,
+
+    #[clap(
+        short,
+        long,
+        value_parser,
+        value_name = "PATH",
+        default_value = "./images"
+    )]
+    images_path: PathBuf,
+
+    /// Key of the course to import the questions into
+    #[clap(long, value_parser, value_name = "KEY")]
+    course: String,
+}
+
+/// A question read from an import file, with where it starts in the file.
+pub struct ImportedQuestion {
+    location: Option<SourceLocation>,
+    raw: RawQuestionData,
+}
+
+impl ImportTarget {
+    /// Adds the questions to the course data and runs it through the formatting pipeline, without
+    /// writing it. Problems in the imported questions are reported at their place in `input_path`,
+    /// and the source of the result is the course data file as it is on disk.
+    async fn merge(
+        &self,
+        input_path: &Path,
+        questions: Vec<ImportedQuestion>,
+        renaming: ImageRenaming,
+    ) -> Result<FormattedCourseData> {
+        let course_path = self.data_path.join(format!("{}.json", self.course));
+        let course_path = fs::canonicalize(&course_path)
+            .with_context(|| format!("Course {} not found", self.course))?;
+        let source = fs::read(&course_path)?;
+        let mut raw_course_data = RawCourseData::from_slice(&source)
+            .with_context(|| format!("Failed to parse {}", course_path.display()))?;
+
+        let existing_count = raw_course_data.questions.len();
+        let (locations, raw_questions): (Vec<_>, Vec<_>) = questions
+            .into_iter()
+            .map(|question| (question.location, question.raw))
+            .unzip();
+
+        raw_course_data.questions.extend(raw_questions);
+
+        let merged = format!("{}\n", serde_json::to_string_pretty(&raw_course_data)?);
+        let mut pipeline_diagnostics = Diagnostics::default();
+        let formatted = CourseData::format_source(
+            course_path.clone(),
+            merged.into_bytes(),
+            self.images_path.clone(),
+            renaming,
+            &mut pipeline_diagnostics,
+        )
+        .await?;
+
+        let mut diagnostics = Diagnostics::default();
+
+        for diagnostic in pipeline_diagnostics.iter() {
+            diagnostics.push(relocate(
+                diagnostic.clone(),
+                existing_count,
+                input_path,
+                &locations,
+            ));
+        }
+
+        eprint!("{diagnostics}");
+        diagnostics.into_result()?;
+
+        let mut formatted = formatted.context("Course data wasn't formatted")?;
+        formatted.source = source;
+
+        Ok(formatted)
+    }
+}
+
+pub async fn import(command: ImportCommand) -> Result<()> {
+    match command {
+        ImportCommand::Csv { target, input_path } => {
+            let questions = self::csv::parse(&input_path)?;
+            let imported_count = questions.len();
+            let formatted = target
+                .merge(&input_path, questions, ImageRenaming::Apply)
+                .await?;
+
+            formatted.write()?;
+
+            println!(
+                "Imported {imported_count} question(s) into {}",
+                display_path(&formatted.path).display()
+            );
+        }
+        ImportCommand::Text {
+            target,
+            options,
+            input_path,
+        } => {
+            let questions = text::parse(&input_path, &options)?;
+            let imported_count = questions.len();
+            let renaming = if options.write {
+                ImageRenaming::Apply
+            } else {
+                ImageRenaming::Plan
+            };
+            let formatted = target.merge(&input_path, questions, renaming).await?;
+
+            print_changes(&formatted);
+
+            if options.write {
+                formatted.write()?;
+
+                println!(
+                    "Imported {imported_count} question(s) into {}",
+                    display_path(&formatted.path).display()
+                );
+            } else {
+                println!("Parsed {imported_count} question(s), pass --write to import them");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Points a diagnostic about an imported question back to where the question is in the import
+/// file, leaving diagnostics about the questions already in the course as they are.
+fn relocate(
+    diagnostic: Diagnostic,
+    existing_count: usize,
+    input_path: &Path,
+    locations: &[Option<SourceLocation>],
+) -> Diagnostic {
+    let question_path = diagnostic
+        .path
+        .strip_prefix("/questions/")
+        .and_then(|path| {
+            let (index, rest) = path.split_once('/').unwrap_or((path, ""));
+
+            Some((index.parse::<usize>().ok()?, rest))
+        });
+
+    match question_path {
+        Some((index, rest)) if index >= existing_count => {
+            // Imported questions get a new ID on every attempt, so it wouldn't help to show it.
+            let mut relocated = Diagnostic {
+                file: Some(input_path.to_owned()),
+                question_id: None,
+                path: if rest.is_empty() {
+                    String::new()
+                } else {
+                    format!("/{rest}")
+                },
+                location: None,
+                ..diagnostic
+            };
+
+            if let Some(location) = locations[index - existing_count].clone() {
+                relocated = relocated.with_location(location);
+            }
+
+            relocated
+        }
+        _ => diagnostic,
+    }
+}
@@ -0,0 +1,531 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use clap::Args;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use medici_data_sync::{
+    Diagnostic, Diagnostics, QuestionKind, QuestionSource, RawQuestionData, RawQuestionOptionData,
+    SourceMap,
+};
+
+use super::ImportedQuestion;
+
+// Only a number followed by a dot or parenthesis and whitespace, so that wrapped text starting
+// with a number, like `2.5 mg/kg` or `2-3 días`, isn't taken for a new question.
+static QUESTION_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*(\d+)[.)](?:\s+|$)(.*)$").unwrap());
+static OPTION_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*([a-eA-E])\s*[.)-]\s*(.*)$").unwrap());
+static ANSWER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(\d+)\s*[.):-]?\s*([a-zA-Z](?:\s*,\s*[a-zA-Z])*)\b").unwrap());
+
+#[derive(Args, Clone, Debug)]
+pub struct TextImportOptions {
+    /// Key of the evaluation the exam belongs to
+    #[clap(long, value_parser, value_name = "KEY")]
+    evaluation: String,
+
+    /// Source of the exam: partial, exam, self_assessment or other
+    #[clap(long, value_parser, value_name = "SOURCE")]
+    source: QuestionSource,
+
+    /// Date the exam was taken, like 2022-07-01
+    #[clap(long, value_parser, value_name = "DATE")]
+    asked_at: Option<NaiveDate>,
+
+    /// Text at the start or end of an option marking it as correct
+    #[clap(long, value_parser, value_name = "TEXT", default_value = "*")]
+    correct_marker: String,
+
+    /// Text starting the line after which the answer key is, ignoring case
+    #[clap(long, value_parser, value_name = "TEXT", default_value = "Respuestas")]
+    answer_key_heading: String,
+
+    /// Write the imported questions to the course, instead of only previewing the changes
+    #[clap(long)]
+    pub write: bool,
+}
+
+struct ParsedQuestion {
+    number: usize,
+    line: usize,
+    text: String,
+    options: Vec<ParsedOption>,
+}
+
+struct ParsedOption {
+    letter: char,
+    text: String,
+    correct: bool,
+}
+
+struct Answer {
+    line: usize,
+    letters: Vec<char>,
+}
+
+/// Reads the numbered questions and the answer key, failing with every problem found.
+pub fn parse(input_path: &Path, options: &TextImportOptions) -> Result<Vec<ImportedQuestion>> {
+    let input = fs::read(input_path)?;
+    let mut diagnostics = Diagnostics::default();
+    let questions = parse_source(input_path, &input, options, &mut diagnostics);
+
+    eprint!("{diagnostics}");
+    diagnostics.into_result()?;
+
+    Ok(questions)
+}
+
+fn parse_source(
+    input_path: &Path,
+    input: &[u8],
+    options: &TextImportOptions,
+    diagnostics: &mut Diagnostics,
+) -> Vec<ImportedQuestion> {
+    let source_map = SourceMap::lines(input);
+    let text = String::from_utf8_lossy(input);
+
+    let mut error = |line: usize, message: String| {
+        let mut diagnostic = Diagnostic::error("", message).with_file(input_path.to_owned());
+
+        if let Some(location) = source_map.locate_line_byte_column(line, 1) {
+            diagnostic = diagnostic.with_location(location);
+        }
+
+        diagnostics.push(diagnostic);
+    };
+
+    let answer_key_heading = options.answer_key_heading.to_lowercase();
+    let mut questions: Vec<ParsedQuestion> = vec![];
+    let mut answers: BTreeMap<usize, Answer> = BTreeMap::new();
+    let mut in_answer_key = false;
+    // Set after a question that can't be numbered, whose options are then left out.
+    let mut is_skipping_question = false;
+
+    for (index, line_text) in text.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = line_text.trim();
+
+        if !in_answer_key && trimmed.to_lowercase().starts_with(&answer_key_heading) {
+            in_answer_key = true;
+        }
+
+        if in_answer_key {
+            for captures in ANSWER_REGEX.captures_iter(line_text) {
+                let letters = captures[2]
+                    .split(',')
+                    .map(|letter| letter.trim().to_ascii_lowercase().chars().next().unwrap())
+                    .collect();
+
+                match captures[1].parse() {
+                    Ok(number) => {
+                        answers.insert(number, Answer { line, letters });
+                    }
+                    Err(_) => error(
+                        line,
+                        format!(
+                            "Answer key refers to question {}, which is too large",
+                            &captures[1]
+                        ),
+                    ),
+                }
+            }
+        } else if let Some(captures) = OPTION_REGEX.captures(line_text) {
+            if is_skipping_question {
+                continue;
+            }
+
+            match questions.last_mut() {
+                Some(question) => {
+                    let (text, correct) = strip_marker(&captures[2], &options.correct_marker);
+
+                    question.options.push(ParsedOption {
+                        letter: captures[1].to_ascii_lowercase().chars().next().unwrap(),
+                        text,
+                        correct,
+                    });
+                }
+                None => error(line, "Option before the first question".to_owned()),
+            }
+        } else if let Some(captures) = QUESTION_REGEX.captures(line_text) {
+            let Ok(number) = captures[1].parse() else {
+                error(
+                    line,
+                    format!("Question number {} is too large", &captures[1]),
+                );
+                is_skipping_question = true;
+
+                continue;
+            };
+
+            is_skipping_question = false;
+
+            if questions.iter().any(|question| question.number == number) {
+                error(line, format!("Question {number} appears more than once"));
+            }
+
+            questions.push(ParsedQuestion {
+                number,
+                line,
+                text: captures[2].trim().to_owned(),
+                options: vec![],
+            });
+        } else if !trimmed.is_empty() && !is_skipping_question {
+            // Lines wrapped by the transcription continue the last option, or the question text.
+            // Anything before the first question, like the exam title, is ignored.
+            if let Some(question) = questions.last_mut() {
+                let text = match question.options.last_mut() {
+                    Some(option) => &mut option.text,
+                    None => &mut question.text,
+                };
+
+                text.push(' ');
+                text.push_str(trimmed);
+            }
+        }
+    }
+
+    for (number, answer) in &answers {
+        let question = questions.iter().find(|question| question.number == *number);
+
+        match question {
+            None => error(
+                answer.line,
+                format!("Answer key refers to question {number}, which isn't in the exam"),
+            ),
+            Some(question) => {
+                for letter in &answer.letters {
+                    if !question
+                        .options
+                        .iter()
+                        .any(|option| option.letter == *letter)
+                    {
+                        error(
+                            answer.line,
+                            format!(
+                                "Answer key refers to option {} of question {number}, which it \
+                                 doesn't have",
+                                letter.to_ascii_uppercase()
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    let mut imported_questions = vec![];
+
+    for mut question in questions {
+        if let Some(answer) = answers.get(&question.number) {
+            let is_marked = question.options.iter().any(|option| option.correct);
+            let conflicts = question
+                .options
+                .iter()
+                .any(|option| option.correct != answer.letters.contains(&option.letter));
+
+            if is_marked && conflicts {
+                error(
+                    question.line,
+                    format!(
+                        "Question {} has marked options that don't match the answer key",
+                        question.number
+                    ),
+                );
+
+                continue;
+            }
+
+            for option in &mut question.options {
+                option.correct = answer.letters.contains(&option.letter);
+            }
+        }
+
+        let correct_count = question
+            .options
+            .iter()
+            .filter(|option| option.correct)
+            .count();
+
+        if correct_count == 0 {
+            error(
+                question.line,
+                format!("Question {} has no correct option", question.number),
+            );
+
+            continue;
+        }
+
+        imported_questions.push(ImportedQuestion {
+            location: source_map.locate_line_byte_column(question.line, 1),
+            raw: RawQuestionData {
+                id: None,
+                kind: if correct_count > 1 {
+                    QuestionKind::MultipleChoice
+                } else {
+                    QuestionKind::SingleChoice
+                },
+                evaluation: options.evaluation.clone(),
+                source: options.source,
+                asked_at: options.asked_at,
+                text: question.text,
+                image: None,
+                options: question
+                    .options
+                    .into_iter()
+                    .map(|option| RawQuestionOptionData {
+                        id: None,
+                        text: option.text,
+                        correct: option.correct.then_some(true),
+                        explanation: None,
+                    })
+                    .collect(),
+                explanation: None,
+                references: vec![],
+                topics: vec![],
+            },
+        });
+    }
+
+    imported_questions
+}
+
+/// Removes the correct marker from either end of the option text, telling whether it was there.
+fn strip_marker(text: &str, marker: &str) -> (String, bool) {
+    let text = text.trim();
+
+    match text
+        .strip_prefix(marker)
+        .or_else(|| text.strip_suffix(marker))
+    {
+        Some(stripped) if !marker.is_empty() => (stripped.trim().to_owned(), true),
+        _ => (text.to_owned(), false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> TextImportOptions {
+        TextImportOptions {
+            evaluation: "parcial_1".to_owned(),
+            source: QuestionSource::Partial,
+            asked_at: None,
+            correct_marker: "*".to_owned(),
+            answer_key_heading: "Respuestas".to_owned(),
+            write: false,
+        }
+    }
+
+    /// The imported questions and the messages and lines of the errors found.
+    fn parse_text(text: &str) -> (Vec<RawQuestionData>, Vec<(String, usize)>) {
+        let mut diagnostics = Diagnostics::default();
+        let questions = parse_source(
+            Path::new("exam.txt"),
+            text.as_bytes(),
+            &options(),
+            &mut diagnostics,
+        );
+        let errors = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let line = diagnostic
+                    .location
+                    .as_ref()
+                    .map_or(0, |location| location.line);
+
+                (diagnostic.message.clone(), line)
+            })
+            .collect();
+
+        (
+            questions.into_iter().map(|question| question.raw).collect(),
+            errors,
+        )
+    }
+
+    fn options_of(question: &RawQuestionData) -> Vec<(&str, bool)> {
+        question
+            .options
+            .iter()
+            .map(|option| (option.text.as_str(), option.correct == Some(true)))
+            .collect()
+    }
+
+    #[test]
+    fn parses_marked_options_and_joins_wrapped_lines() {
+        let (questions, errors) = parse_text(
+            "Parcial 1 - Cardiología\n\
+             \n\
+             1. ¿Cuál es la causa más frecuente\n\
+             de insuficiencia cardíaca?\n\
+             a) Hipertensión *\n\
+             b) Valvulopatía de origen\n   reumático\n\
+             c) * Miocarditis\n",
+        );
+
+        assert_eq!(errors, []);
+        assert_eq!(questions.len(), 1);
+        assert_eq!(
+            questions[0].text,
+            "¿Cuál es la causa más frecuente de insuficiencia cardíaca?"
+        );
+        assert_eq!(
+            options_of(&questions[0]),
+            [
+                ("Hipertensión", true),
+                ("Valvulopatía de origen reumático", false),
+                ("Miocarditis", true),
+            ]
+        );
+        assert_eq!(questions[0].kind, QuestionKind::MultipleChoice);
+    }
+
+    #[test]
+    fn reads_answers_on_the_answer_key_heading_line() {
+        let (questions, errors) = parse_text(
+            "1) One\na) A\nb) B\n\
+             2) Two\nA. A\nB. B\nC. C\n\
+             3. Three\na- A\nb- B\nc- C\n\
+             Respuestas: 1-B, 2) a  3-a,c\n",
+        );
+
+        assert_eq!(errors, []);
+        assert_eq!(options_of(&questions[0]), [("A", false), ("B", true)]);
+        assert_eq!(questions[0].kind, QuestionKind::SingleChoice);
+        assert_eq!(
+            options_of(&questions[1]),
+            [("A", true), ("B", false), ("C", false)]
+        );
+        assert_eq!(
+            options_of(&questions[2]),
+            [("A", true), ("B", false), ("C", true)]
+        );
+        assert_eq!(questions[2].kind, QuestionKind::MultipleChoice);
+    }
+
+    #[test]
+    fn reads_answers_on_lines_after_the_answer_key_heading() {
+        let (questions, errors) =
+            parse_text("1. One\na) A\nb) B\n2. Two\na) A\nb) B\n\nRESPUESTAS\n1 - b\n2 - A, B\n");
+
+        assert_eq!(errors, []);
+        assert_eq!(options_of(&questions[0]), [("A", false), ("B", true)]);
+        assert_eq!(options_of(&questions[1]), [("A", true), ("B", true)]);
+    }
+
+    #[test]
+    fn accepts_marks_agreeing_with_the_answer_key() {
+        let (questions, errors) = parse_text("1. One\na) A *\nb) B\nRespuestas: 1-a\n");
+
+        assert_eq!(errors, []);
+        assert_eq!(options_of(&questions[0]), [("A", true), ("B", false)]);
+    }
+
+    #[test]
+    fn reports_marks_conflicting_with_the_answer_key() {
+        let (questions, errors) = parse_text("1. One\na) A *\nb) B\nRespuestas: 1-b\n");
+
+        assert!(questions.is_empty());
+        assert_eq!(
+            errors,
+            [(
+                "Question 1 has marked options that don't match the answer key".to_owned(),
+                1
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_duplicate_question_numbers() {
+        let (_, errors) = parse_text("1. One\na) A *\nb) B\n1. Again\na) A *\nb) B\n");
+
+        assert_eq!(
+            errors,
+            [("Question 1 appears more than once".to_owned(), 4)]
+        );
+    }
+
+    #[test]
+    fn reports_answer_key_problems() {
+        let (_, errors) = parse_text("1. One\na) A\nb) B\nRespuestas: 1-c, 2-a\n");
+
+        assert_eq!(
+            errors,
+            [
+                (
+                    "Answer key refers to option C of question 1, which it doesn't have".to_owned(),
+                    4
+                ),
+                (
+                    "Answer key refers to question 2, which isn't in the exam".to_owned(),
+                    4
+                ),
+                ("Question 1 has no correct option".to_owned(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_options_before_the_first_question() {
+        let (_, errors) = parse_text("Parcial\na) A\n1. One\na) A *\nb) B\n");
+
+        assert_eq!(errors, [("Option before the first question".to_owned(), 2)]);
+    }
+
+    #[test]
+    fn reports_numbers_too_large_instead_of_failing() {
+        let (questions, errors) = parse_text(
+            "99999999999999999999. Huge\na) A *\nb) B\n\
+             1. One\na) A *\nb) B\n\
+             Respuestas: 1-a, 99999999999999999999-b\n",
+        );
+
+        assert_eq!(questions.len(), 1);
+        assert_eq!(
+            errors,
+            [
+                (
+                    "Question number 99999999999999999999 is too large".to_owned(),
+                    1
+                ),
+                (
+                    "Answer key refers to question 99999999999999999999, which is too large"
+                        .to_owned(),
+                    7
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn continues_options_and_text_starting_with_numbers() {
+        let (questions, errors) = parse_text(
+            "1. Dosis de carga de\n2 mg/kg de amiodarona:\n\
+             a) 2.5 mg/kg *\n\
+             b) 5 mg/kg en\n2-3 días\n\
+             c) 10 mg/kg cada\n8 horas\n",
+        );
+
+        assert_eq!(errors, []);
+        assert_eq!(questions.len(), 1);
+        assert_eq!(
+            questions[0].text,
+            "Dosis de carga de 2 mg/kg de amiodarona:"
+        );
+        assert_eq!(
+            options_of(&questions[0]),
+            [
+                ("2.5 mg/kg", true),
+                ("5 mg/kg en 2-3 días", false),
+                ("10 mg/kg cada 8 horas", false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use medici_data_sync::{read_data_dir, CourseData, Diagnostic, Diagnostics, ImageRenaming};
+
+pub async fn lint(data_path: PathBuf, images_path: PathBuf) -> Result<()> {
+    let entries = read_data_dir(data_path)?;
+    let mut diagnostics = Diagnostics::default();
+
+    for dir_entry in entries {
+        let formatted = CourseData::load_formatted(
+            dir_entry?,
+            images_path.clone(),
+            ImageRenaming::Plan,
+            &mut diagnostics,
+        )
+        .await?;
+
+        let formatted = match formatted {
+            Some(formatted) if !formatted.is_formatted() => formatted,
+            _ => continue,
+        };
+
+        diagnostics.push(
+            Diagnostic::error("", "File is not formatted, run `data-sync format`")
+                .with_file(formatted.path.clone()),
+        );
+
+        for image_rename in formatted.image_renames {
+            diagnostics.push(
+                Diagnostic::error(
+                    "",
+                    format!("Image should be renamed to {}", image_rename.to.display()),
+                )
+                .with_file(image_rename.from),
+            );
+        }
+    }
+
+    eprint!("{diagnostics}");
+
+    diagnostics.into_result()
+}
@@ -1,3 +1,4 @@
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 use anyhow::Result;
@@ -5,7 +6,16 @@ use clap::{Parser, Subcommand};
 use secrecy::Secret;
 use url::Url;
 
+mod duplicates;
+mod export;
 mod format;
+mod images;
+mod import;
+mod lint;
+mod mock_engine;
+mod plan;
+mod render;
+mod schema;
 mod sync;
 
 #[derive(Parser, Clone, Debug)]
@@ -28,22 +38,54 @@ impl Synchronizer {
                 images_path,
                 engine_url,
                 engine_secret,
-                sync_images_bucket,
+                options,
             } => {
-                sync::sync(
-                    data_path,
-                    images_path,
-                    engine_url,
-                    engine_secret,
-                    sync_images_bucket,
-                )
-                .await?;
+                sync::sync(data_path, images_path, engine_url, engine_secret, *options).await?;
             }
             Command::Format {
                 data_path,
                 images_path,
+                check,
             } => {
-                format::format(data_path, images_path).await?;
+                format::format(data_path, images_path, check).await?;
+            }
+            Command::Lint {
+                data_path,
+                images_path,
+            } => {
+                lint::lint(data_path, images_path).await?;
+            }
+            Command::Duplicates {
+                data_path,
+                images_path,
+                min_score,
+            } => {
+                duplicates::duplicates(data_path, images_path, min_score).await?;
+            }
+            Command::Export { command } => {
+                export::export(command).await?;
+            }
+            Command::Import { command } => {
+                import::import(command).await?;
+            }
+            Command::Render {
+                source,
+                options,
+                output_path,
+            } => {
+                render::render(source, options, output_path).await?;
+            }
+            Command::Schema { output_path } => {
+                schema::schema(output_path)?;
+            }
+            Command::MockEngine {
+                address,
+                state_path,
+                engine_secret,
+                images_bucket_name,
+            } => {
+                mock_engine::mock_engine(address, state_path, engine_secret, images_bucket_name)
+                    .await?;
             }
         }
 
@@ -71,6 +113,30 @@ enum Command {
             default_value = "./images"
         )]
         images_path: PathBuf,
+
+        /// Print the changes formatting would make instead of applying them, failing if any
+        #[clap(long)]
+        check: bool,
+    },
+    /// Check the data and its formatting without writing any file
+    Lint {
+        #[clap(
+            short,
+            long,
+            value_parser,
+            value_name = "PATH",
+            default_value = "./data"
+        )]
+        data_path: PathBuf,
+
+        #[clap(
+            short,
+            long,
+            value_parser,
+            value_name = "PATH",
+            default_value = "./images"
+        )]
+        images_path: PathBuf,
     },
     Sync {
         #[clap(
@@ -91,8 +157,8 @@ enum Command {
         )]
         images_path: PathBuf,
 
-        #[clap(long)]
-        sync_images_bucket: bool,
+        #[clap(flatten)]
+        options: Box<sync::SyncOptions>,
 
         #[clap(long, value_parser, value_name = "ENGINE_URL", env = "ENGINE_URL")]
         engine_url: Url,
@@ -105,6 +171,94 @@ enum Command {
         )]
         engine_secret: Secret<String>,
     },
+    /// Report questions that look like near-duplicates of each other, within and across courses
+    Duplicates {
+        #[clap(
+            short,
+            long,
+            value_parser,
+            value_name = "PATH",
+            default_value = "./data"
+        )]
+        data_path: PathBuf,
+
+        #[clap(
+            short,
+            long,
+            value_parser,
+            value_name = "PATH",
+            default_value = "./images"
+        )]
+        images_path: PathBuf,
+
+        /// Minimum similarity, from 0 to 1, for a pair of questions to be reported
+        #[clap(long, value_parser, value_name = "SCORE", default_value_t = 0.8)]
+        min_score: f64,
+    },
+    /// Export the questions to formats used by other study tools
+    Export {
+        #[clap(subcommand)]
+        command: export::ExportCommand,
+    },
+    /// Import questions into a course from formats used to transcribe them
+    Import {
+        #[clap(subcommand)]
+        command: import::ImportCommand,
+    },
+    /// Render the questions as a printable HTML exam
+    Render {
+        #[clap(flatten)]
+        source: export::ExportSource,
+
+        #[clap(flatten)]
+        options: render::RenderOptions,
+
+        /// Write the HTML to the given path instead of printing it
+        #[clap(short, long, value_parser, value_name = "PATH")]
+        output_path: Option<PathBuf>,
+    },
+    /// Print the JSON Schema of course data files
+    Schema {
+        /// Write the schema to the given path instead of printing it
+        #[clap(short, long, value_parser, value_name = "PATH")]
+        output_path: Option<PathBuf>,
+    },
+    /// Serve the engine admin sync endpoints locally, keeping their state in a JSON file
+    MockEngine {
+        #[clap(
+            short,
+            long,
+            value_parser,
+            value_name = "ADDRESS",
+            default_value = "127.0.0.1:8080"
+        )]
+        address: SocketAddr,
+
+        #[clap(
+            short,
+            long,
+            value_parser,
+            value_name = "PATH",
+            default_value = "./mock-engine.json"
+        )]
+        state_path: PathBuf,
+
+        #[clap(
+            long,
+            value_parser,
+            value_name = "ENGINE_SECRET",
+            env = "ENGINE_SECRET"
+        )]
+        engine_secret: Secret<String>,
+
+        #[clap(
+            long,
+            value_parser,
+            value_name = "NAME",
+            default_value = "medici-images"
+        )]
+        images_bucket_name: String,
+    },
 }
 
 #[tokio::main]
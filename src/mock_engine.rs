@@ -0,0 +1,152 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::{
+    extract::{DefaultBodyLimit, State},
+    http::{header, HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use secrecy::{ExposeSecret, Secret};
+use tokio::sync::Mutex;
+
+use medici_data_sync::{write_data, SyncData, SyncMetadata};
+
+struct MockEngine {
+    engine_secret: Secret<String>,
+    state_path: PathBuf,
+    sync_metadata: Mutex<SyncMetadata>,
+}
+
+impl MockEngine {
+    fn authorize(&self, headers: &HeaderMap) -> Result<(), StatusCode> {
+        let expected = format!("Bearer {}", self.engine_secret.expose_secret());
+
+        match headers.get(header::AUTHORIZATION) {
+            Some(authorization) if authorization.as_bytes() == expected.as_bytes() => Ok(()),
+            _ => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+
+    fn persist(&self, sync_metadata: &SyncMetadata) -> Result<()> {
+        write_data(
+            self.state_path.clone(),
+            serde_json::to_string_pretty(sync_metadata)?,
+        )
+    }
+}
+
+pub async fn mock_engine(
+    address: SocketAddr,
+    state_path: PathBuf,
+    engine_secret: Secret<String>,
+    images_bucket_name: String,
+) -> Result<()> {
+    let mut sync_metadata: SyncMetadata = if state_path.exists() {
+        serde_json::from_slice(&tokio::fs::read(&state_path).await?)?
+    } else {
+        Default::default()
+    };
+    sync_metadata.images_bucket_name = images_bucket_name;
+
+    let engine = Arc::new(MockEngine {
+        engine_secret,
+        state_path,
+        sync_metadata: Mutex::new(sync_metadata),
+    });
+
+    let app = Router::new()
+        .route("/admin/sync-metadata", get(get_sync_metadata))
+        .route("/admin/sync-data", post(post_sync_data))
+        .layer(DefaultBodyLimit::disable())
+        .with_state(engine);
+
+    println!("Mock engine listening on http://{address}/");
+
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+async fn get_sync_metadata(
+    State(engine): State<Arc<MockEngine>>,
+    headers: HeaderMap,
+) -> Result<Json<SyncMetadata>, StatusCode> {
+    engine.authorize(&headers)?;
+
+    Ok(Json(engine.sync_metadata.lock().await.clone()))
+}
+
+async fn post_sync_data(
+    State(engine): State<Arc<MockEngine>>,
+    headers: HeaderMap,
+    Json(data): Json<SyncData>,
+) -> Result<StatusCode, StatusCode> {
+    engine.authorize(&headers)?;
+
+    let mut sync_metadata = engine.sync_metadata.lock().await;
+    sync_metadata.apply(&data);
+
+    engine
+        .persist(&sync_metadata)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use medici_data_sync::SyncPlan;
+
+    use super::*;
+
+    fn headers(secret: &str) -> HeaderMap {
+        HeaderMap::from_iter([(
+            header::AUTHORIZATION,
+            format!("Bearer {secret}").parse().unwrap(),
+        )])
+    }
+
+    #[tokio::test]
+    async fn sync_data_is_applied_to_the_metadata_and_persisted() {
+        let state_path = env::temp_dir().join(format!("mock-engine-{}.json", process::id()));
+        let mut sync_metadata = SyncMetadata::default();
+        sync_metadata
+            .courses_metadata
+            .insert("cardio".to_owned(), "hash".to_owned());
+
+        let engine = Arc::new(MockEngine {
+            engine_secret: Secret::new("secret".to_owned()),
+            state_path: state_path.clone(),
+            sync_metadata: Mutex::new(sync_metadata.clone()),
+        });
+
+        assert_eq!(
+            get_sync_metadata(State(engine.clone()), headers("wrong"))
+                .await
+                .err(),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+
+        let data = SyncPlan::compute(&[], sync_metadata).into();
+        let status = post_sync_data(State(engine.clone()), headers("secret"), Json(data)).await;
+
+        assert_eq!(status, Ok(StatusCode::OK));
+
+        let Json(sync_metadata) = get_sync_metadata(State(engine), headers("secret"))
+            .await
+            .unwrap();
+        let persisted: SyncMetadata =
+            serde_json::from_slice(&fs::read(&state_path).unwrap()).unwrap();
+        fs::remove_file(state_path).unwrap();
+
+        assert!(sync_metadata.courses_metadata.is_empty());
+        assert!(persisted.courses_metadata.is_empty());
+    }
+}
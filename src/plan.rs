@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use medici_data_sync::{write_data, ChangeCounts, QuestionData, SyncPlan};
+
+const SNIPPET_LENGTH: usize = 60;
+
+pub fn report(plan: &SyncPlan) -> String {
+    let summary = plan.summary();
+    let mut report = String::new();
+
+    for (title, counts) in [
+        ("Courses", summary.courses),
+        ("Course evaluations", summary.course_evaluations),
+        ("Course topics", summary.course_topics),
+        ("Questions", summary.questions),
+        ("Question options", summary.question_options),
+    ] {
+        writeln!(report, "{title}: {}", format_counts(counts)).unwrap();
+    }
+
+    match summary.images {
+        Some(counts) => writeln!(report, "Images: {}", format_counts(counts)).unwrap(),
+        None => writeln!(report, "Images: not tracked by the engine").unwrap(),
+    }
+
+    let mut questions_by_course: HashMap<&str, Vec<(char, &QuestionData)>> = HashMap::new();
+
+    for (marker, questions) in [('+', &plan.questions.added), ('~', &plan.questions.changed)] {
+        for question in questions {
+            questions_by_course
+                .entry(question.course_key.as_deref().unwrap_or_default())
+                .or_default()
+                .push((marker, question));
+        }
+    }
+
+    let mut course_keys: Vec<_> = questions_by_course.keys().copied().collect();
+    course_keys.sort_unstable();
+
+    for course_key in course_keys {
+        let questions = &questions_by_course[course_key];
+        let added_count = questions
+            .iter()
+            .filter(|(marker, _)| *marker == '+')
+            .count();
+
+        writeln!(
+            report,
+            "\n{course_key}: {added_count} question(s) added, {} changed",
+            questions.len() - added_count
+        )
+        .unwrap();
+
+        for (marker, question) in questions {
+            writeln!(
+                report,
+                "  {marker} {} {}",
+                question.id,
+                snippet(&question.text)
+            )
+            .unwrap();
+        }
+    }
+
+    for (title, keys) in [
+        ("Deleted courses", &plan.courses.deleted),
+        (
+            "Deleted course evaluations",
+            &plan.course_evaluations.deleted,
+        ),
+        ("Deleted course topics", &plan.course_topics.deleted),
+    ] {
+        write_deleted(&mut report, title, keys);
+    }
+
+    if let Some(images) = &plan.images {
+        write_deleted(&mut report, "Deleted images", &images.deleted);
+    }
+
+    for (title, ids) in [
+        ("Deleted questions", &plan.questions.deleted),
+        ("Deleted question options", &plan.question_options.deleted),
+    ] {
+        write_deleted(&mut report, title, ids);
+    }
+
+    report
+}
+
+pub fn write_json(plan: &SyncPlan, path: PathBuf) -> Result<()> {
+    write_data(path, serde_json::to_string_pretty(plan)?)
+}
+
+fn format_counts(counts: ChangeCounts) -> String {
+    format!(
+        "{} added, {} changed, {} deleted, {} unchanged",
+        counts.added, counts.changed, counts.deleted, counts.unchanged
+    )
+}
+
+fn write_deleted<K: ToString>(report: &mut String, title: &str, keys: &[K]) {
+    if keys.is_empty() {
+        return;
+    }
+
+    writeln!(report, "\n{title}:").unwrap();
+
+    let mut keys: Vec<_> = keys.iter().map(ToString::to_string).collect();
+    keys.sort_unstable();
+
+    for key in keys {
+        writeln!(report, "  - {key}").unwrap();
+    }
+}
+
+pub fn snippet(text: &str) -> String {
+    if text.chars().count() > SNIPPET_LENGTH {
+        format!(
+            "{}...",
+            text.chars().take(SNIPPET_LENGTH).collect::<String>()
+        )
+    } else {
+        text.to_owned()
+    }
+}
@@ -0,0 +1,286 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use clap::Args;
+use rand::seq::{index, SliceRandom};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use medici_data_sync::{CourseData, QuestionData, WithImage};
+
+use crate::export::{
+    escape_html, explanation_html, image_file_name, questions_by_evaluation, ExportSource,
+};
+use crate::images::content_type;
+
+const STYLE: &str = "
+    body { font-family: serif; font-size: 11pt; max-width: 48em; margin: 2em auto; }
+    h1, h2 { font-family: sans-serif; }
+    .question { break-inside: avoid; margin-bottom: 1.5em; }
+    .question img { display: block; max-width: 100%; max-height: 20em; margin: 0.5em 0; }
+    .options { list-style-type: upper-alpha; }
+    .answer-key { break-before: page; }
+    footer { margin-top: 3em; font-size: 9pt; color: gray; }
+";
+
+#[derive(Args, Clone, Debug)]
+pub struct RenderOptions {
+    /// Append the correct options and explanations of every question
+    #[clap(long)]
+    answer_key: bool,
+
+    /// Render only this many questions, picked at random
+    #[clap(long, value_parser, value_name = "COUNT")]
+    sample: Option<usize>,
+
+    /// Seed for picking questions and shuffling options, to render the same form again
+    #[clap(long, value_parser, value_name = "SEED")]
+    seed: Option<u64>,
+
+    /// Title of the rendered document, instead of the course names
+    #[clap(long, value_parser, value_name = "TITLE")]
+    title: Option<String>,
+}
+
+/// A section of questions from the same evaluation.
+struct Section<'a> {
+    course_data: &'a CourseData,
+    evaluation_name: &'a str,
+    questions: Vec<QuestionData>,
+}
+
+/// Renders the questions into a self-contained HTML document, meant to be printed.
+pub async fn render(
+    source: ExportSource,
+    options: RenderOptions,
+    output_path: Option<PathBuf>,
+) -> Result<()> {
+    let courses_data = source.load().await?;
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let mut sections: Vec<_> = courses_data
+        .iter()
+        .flat_map(|course_data| {
+            questions_by_evaluation(course_data)
+                .into_iter()
+                .map(move |(evaluation, questions)| Section {
+                    course_data,
+                    evaluation_name: &evaluation.name,
+                    questions: questions.into_iter().cloned().collect(),
+                })
+        })
+        .collect();
+
+    if let Some(sample) = options.sample {
+        sections = sample_questions(sections, sample, &mut rng)?;
+    }
+
+    // Options are sorted with the correct ones first, which a printed form must not give away.
+    for section in &mut sections {
+        for question in &mut section.questions {
+            question.question_options.shuffle(&mut rng);
+        }
+    }
+
+    let title = options.title.unwrap_or_else(|| {
+        let mut course_names: Vec<_> = sections
+            .iter()
+            .map(|section| section.course_data.name.as_str())
+            .collect();
+        course_names.dedup();
+
+        course_names.join(", ")
+    });
+
+    let html = render_html(
+        &title,
+        &sections,
+        &source.images_path,
+        options.answer_key,
+        seed,
+    )?;
+
+    match output_path {
+        Some(output_path) => {
+            fs::write(&output_path, html)?;
+            eprintln!("Rendered {} with seed {seed}", output_path.display());
+        }
+        None => print!("{html}"),
+    }
+
+    Ok(())
+}
+
+/// Keeps `sample` questions picked at random, in their original order.
+fn sample_questions<'a>(
+    sections: Vec<Section<'a>>,
+    sample: usize,
+    rng: &mut ChaCha8Rng,
+) -> Result<Vec<Section<'a>>> {
+    let question_count: usize = sections.iter().map(|section| section.questions.len()).sum();
+
+    if sample > question_count {
+        bail!("Can't sample {sample} questions out of {question_count}");
+    }
+
+    let mut picked = vec![false; question_count];
+
+    for index in index::sample(rng, question_count, sample) {
+        picked[index] = true;
+    }
+
+    let mut picked = picked.into_iter();
+
+    Ok(sections
+        .into_iter()
+        .map(|mut section| {
+            section
+                .questions
+                .retain(|_| picked.next().unwrap_or_default());
+            section
+        })
+        .filter(|section| !section.questions.is_empty())
+        .collect())
+}
+
+fn render_html(
+    title: &str,
+    sections: &[Section],
+    images_path: &Path,
+    answer_key: bool,
+    seed: u64,
+) -> Result<String> {
+    let mut html = String::new();
+    let mut course_key = None;
+    let mut number = 0;
+    let is_multiple_courses = sections
+        .iter()
+        .any(|section| section.course_data.key != sections[0].course_data.key);
+
+    writeln!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>",
+        title = escape_html(title)
+    )?;
+
+    for section in sections {
+        if is_multiple_courses && course_key != Some(&section.course_data.key) {
+            course_key = Some(&section.course_data.key);
+
+            writeln!(html, "<h1>{}</h1>", escape_html(&section.course_data.name))?;
+        }
+
+        writeln!(html, "<h2>{}</h2>", escape_html(section.evaluation_name))?;
+
+        for question in &section.questions {
+            number += 1;
+
+            writeln!(html, "{}", question_html(number, question, images_path)?)?;
+        }
+    }
+
+    if answer_key {
+        writeln!(html, "{}", answer_key_html(sections))?;
+    }
+
+    writeln!(html, "<footer>Seed {seed}</footer>\n</body>\n</html>")?;
+
+    Ok(html)
+}
+
+fn question_html(number: usize, question: &QuestionData, images_path: &Path) -> Result<String> {
+    let mut html = format!(
+        "<div class=\"question\">\n<p><strong>{number}.</strong> {}</p>",
+        escape_html(&question.text)
+    );
+
+    if let (Some(image_path), Some(image_file_name)) =
+        (question.full_image_path(), image_file_name(question))
+    {
+        let image = fs::read(images_path.join(image_path))?;
+
+        write!(
+            html,
+            "\n<img src=\"data:{};base64,{}\" alt=\"{}\">",
+            content_type(Path::new(&image_file_name))?,
+            BASE64.encode(image),
+            escape_html(&image_file_name)
+        )?;
+    }
+
+    html.push_str("\n<ol class=\"options\">");
+
+    for option in &question.question_options {
+        write!(html, "\n<li>{}</li>", escape_html(&option.text))?;
+    }
+
+    html.push_str("\n</ol>\n</div>");
+
+    Ok(html)
+}
+
+fn answer_key_html(sections: &[Section]) -> String {
+    let mut html = String::from("<section class=\"answer-key\">\n<h1>Answer key</h1>\n<ol>");
+
+    for question in sections.iter().flat_map(|section| &section.questions) {
+        let letters: Vec<_> = question
+            .question_options
+            .iter()
+            .enumerate()
+            .filter(|(_, option)| option.correct)
+            .map(|(index, _)| option_letter(index).to_string())
+            .collect();
+
+        html.push_str(&format!(
+            "\n<li><strong>{}</strong>{}</li>",
+            if letters.is_empty() {
+                "-".to_owned()
+            } else {
+                letters.join(", ")
+            },
+            explanation_html(question)
+        ));
+    }
+
+    html.push_str("\n</ol>\n</section>");
+
+    html
+}
+
+fn option_letter(index: usize) -> char {
+    (b'A' + index as u8) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use medici_data_sync::test_data::{course, question};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn question_images_are_inlined_with_their_content_type() {
+        let images_path = env::temp_dir().join(format!("render-{}", process::id()));
+        fs::create_dir_all(images_path.join("spyh")).unwrap();
+        fs::write(images_path.join("spyh/question.avif"), b"avif").unwrap();
+
+        let mut question = question(1, "Question");
+        question["image"] = json!("question.avif");
+        let course_data = course("spyh", vec![question]);
+
+        let html = question_html(1, &course_data.questions[0], &images_path);
+        fs::remove_dir_all(images_path).unwrap();
+
+        assert!(html.unwrap().contains(&format!(
+            "src=\"data:image/avif;base64,{}\"",
+            BASE64.encode("avif")
+        )));
+    }
+}
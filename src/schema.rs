@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use medici_data_sync::{write_data, RawCourseData};
+
+pub fn schema(output_path: Option<PathBuf>) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&RawCourseData::json_schema())?;
+
+    match output_path {
+        Some(output_path) => write_data(output_path, schema),
+        None => {
+            println!("{schema}");
+
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn committed_schema_is_up_to_date() {
+        let schema = serde_json::to_string_pretty(&RawCourseData::json_schema()).unwrap();
+
+        assert!(
+            include_str!("../data.schema.json") == format!("{schema}\n"),
+            "data.schema.json is outdated, run `data-sync schema -o data.schema.json`"
+        );
+    }
+}
@@ -1,109 +1,137 @@
 use std::path::PathBuf;
 
 use anyhow::{bail, Result};
+use clap::Args;
 use secrecy::{ExposeSecret, Secret};
 use url::Url;
 
-use medici_data_sync::{load_courses_data_and_write_formatted, SyncData, SyncMetadata};
+use medici_data_sync::{
+    load_courses_data, load_courses_data_and_write_formatted, CourseData, Diagnostics, EntityPlan,
+    SyncData, SyncMetadata, SyncPlan,
+};
+
+use crate::images::ImagesBucket;
+use crate::plan;
+
+#[derive(Args, Clone, Debug)]
+pub struct SyncOptions {
+    #[clap(long)]
+    sync_images_bucket: bool,
+
+    /// Region of the images bucket
+    #[clap(long, value_parser, env = "AWS_REGION", default_value = "sa-east-1")]
+    images_bucket_region: String,
+
+    /// Endpoint of an S3-compatible service hosting the images bucket, instead of AWS
+    #[clap(long, value_parser, value_name = "URL", env = "IMAGES_BUCKET_ENDPOINT")]
+    images_bucket_endpoint: Option<Url>,
+
+    /// Print the changes that would be synced without applying them
+    #[clap(long, alias = "dry-run")]
+    plan: bool,
+
+    /// Write the planned changes as JSON to the given path, without applying them
+    #[clap(long, value_parser, value_name = "PATH")]
+    plan_json: Option<PathBuf>,
+
+    /// Sync even if whole courses or too many questions would be deleted
+    #[clap(long, alias = "force")]
+    allow_deletions: bool,
+
+    /// Maximum percentage of the engine's questions a sync may delete
+    #[clap(long, value_parser, value_name = "PERCENT", default_value_t = 10.0)]
+    max_deleted_questions_percent: f64,
+}
 
 pub async fn sync(
     data_path: PathBuf,
     images_path: PathBuf,
     engine_url: Url,
     engine_secret: Secret<String>,
-    sync_images_bucket: bool,
+    options: SyncOptions,
 ) -> Result<()> {
     let engine_client = engine_client(engine_secret)?;
-    let mut sync_metadata = sync_metadata(&engine_client, engine_url.clone()).await?;
-
-    let mut courses_to_sync = vec![];
-    let mut questions_to_sync = vec![];
-    let mut question_options_to_sync = vec![];
-    let mut course_evaluations_to_sync = vec![];
-
-    let mut courses_data =
-        load_courses_data_and_write_formatted(data_path, images_path.clone()).await?;
-
-    for mut course_data in courses_data.drain(..) {
-        let skip_course = match sync_metadata.courses_metadata.remove(&course_data.key) {
-            Some(course_hash) if course_hash == course_data.hash => true,
-            _ => false,
-        };
-
-        for course_evaluation_data in course_data.evaluations.drain(..) {
-            match sync_metadata
-                .course_evaluations_metadata
-                .remove(&course_evaluation_data.full_key())
-            {
-                Some(question_option_hash)
-                    if question_option_hash == course_evaluation_data.hash => {}
-                _ => course_evaluations_to_sync.push(course_evaluation_data),
-            }
-        }
+    let sync_metadata = sync_metadata(&engine_client, engine_url.clone()).await?;
+    let images_bucket_name = sync_metadata.images_bucket_name.clone();
+
+    let plan_only = options.plan || options.plan_json.is_some();
+
+    // Planning is meant for checks on unmerged changes, so it must leave the data files as they are.
+    let mut diagnostics = Diagnostics::default();
+    let courses_data = if plan_only {
+        load_courses_data(data_path, images_path.clone(), &mut diagnostics).await?
+    } else {
+        load_courses_data_and_write_formatted(data_path, images_path.clone(), &mut diagnostics)
+            .await?
+    };
+
+    eprint!("{diagnostics}");
+    diagnostics.into_result()?;
+
+    let sync_plan = SyncPlan::compute(&courses_data, sync_metadata);
+
+    if plan_only {
+        print!("{}", plan::report(&sync_plan));
 
-        for mut question_data in course_data.questions.drain(..) {
-            let skip_question = match sync_metadata.questions_metadata.remove(&question_data.id) {
-                Some(question_hash) if question_hash == question_data.hash => true,
-                _ if !skip_course => false,
-                _ => true,
-            };
-
-            for question_option_data in question_data.question_options.drain(..) {
-                match sync_metadata
-                    .question_options_metadata
-                    .remove(&question_option_data.id)
-                {
-                    Some(question_option_hash)
-                        if question_option_hash == question_option_data.hash => {}
-                    _ => question_options_to_sync.push(question_option_data),
-                }
-            }
-
-            if !skip_question {
-                questions_to_sync.push(question_data);
-            }
+        if let Some(plan_json) = options.plan_json {
+            plan::write_json(&sync_plan, plan_json)?;
         }
+    }
+
+    let excessive_deletions = sync_plan.excessive_deletions(options.max_deleted_questions_percent);
 
-        if !skip_course {
-            courses_to_sync.push(course_data.clone());
+    if !excessive_deletions.is_empty() && !options.allow_deletions {
+        if !plan_only {
+            eprint!("{}", plan::report(&sync_plan));
         }
+
+        bail!(
+            "Refusing to sync:\n{}\nPass --allow-deletions to sync anyway",
+            excessive_deletions
+                .iter()
+                .map(|excessive_deletion| format!("  - {excessive_deletion}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
     }
 
-    let courses_to_delete = sync_metadata.courses_metadata.keys().cloned().collect();
-    let questions_to_delete = sync_metadata.questions_metadata.keys().cloned().collect();
-    let question_options_to_delete = sync_metadata
-        .question_options_metadata
-        .keys()
-        .cloned()
-        .collect();
-
-    let course_evaluations_to_delete = sync_metadata
-        .course_evaluations_metadata
-        .keys()
-        .cloned()
-        .collect();
-
-    sync_data(
-        &engine_client,
-        engine_url.clone(),
-        SyncData {
-            courses_to_sync,
-            courses_to_delete,
-
-            questions_to_sync,
-            questions_to_delete,
-
-            question_options_to_sync,
-            question_options_to_delete,
-
-            course_evaluations_to_sync,
-            course_evaluations_to_delete,
-        },
-    )
-    .await?;
-
-    if sync_images_bucket {
-        sync_images(images_path, &sync_metadata.images_bucket_name).await?;
+    if plan_only {
+        return Ok(());
+    }
+
+    let mut sync_plan = sync_plan;
+    let images: Vec<_> = courses_data.iter().flat_map(CourseData::images).collect();
+
+    let images_bucket = if options.sync_images_bucket {
+        let images_bucket = ImagesBucket::new(
+            &images_bucket_name,
+            options.images_bucket_region,
+            options.images_bucket_endpoint,
+        )?;
+
+        images_bucket
+            .upload(images_path, &images, sync_plan.images.as_ref())
+            .await?;
+
+        // An engine that can track images but doesn't yet starts from the ones just uploaded.
+        sync_plan.images.get_or_insert_with(|| EntityPlan {
+            added: images.clone(),
+            ..Default::default()
+        });
+
+        Some(images_bucket)
+    } else {
+        // Leave images out so the engine only tracks the ones that were actually uploaded.
+        sync_plan.images = None;
+
+        None
+    };
+
+    sync_data(&engine_client, engine_url.clone(), sync_plan.into()).await?;
+
+    // Only once the engine stopped pointing to them, so a failed sync leaves every image in place.
+    if let Some(images_bucket) = images_bucket {
+        images_bucket.prune(&images).await?;
     }
 
     Ok(())
@@ -127,7 +155,13 @@ fn engine_client(engine_secret: Secret<String>) -> Result<reqwest::Client> {
 async fn sync_metadata(client: &reqwest::Client, engine_url: Url) -> Result<SyncMetadata> {
     let url = engine_url.join("admin/sync-metadata")?;
 
-    Ok(client.get(url).send().await?.json().await?)
+    Ok(client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
 }
 
 async fn sync_data(client: &reqwest::Client, engine_url: Url, data: SyncData) -> Result<()> {
@@ -140,16 +174,3 @@ async fn sync_data(client: &reqwest::Client, engine_url: Url, data: SyncData) ->
         bail!("Error {}", response.status())
     }
 }
-
-async fn sync_images(images_path: PathBuf, bucket_name: &str) -> Result<()> {
-    tokio::process::Command::new("aws")
-        .arg("s3")
-        .arg("sync")
-        .arg(images_path)
-        .arg(format!("s3://{bucket_name}"))
-        .arg("--delete")
-        .status()
-        .await?;
-
-    Ok(())
-}